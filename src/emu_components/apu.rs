@@ -0,0 +1,114 @@
+use super::boot::boot_rom_mapped;
+use super::common::bit;
+use super::emu::emu_model;
+
+/*
+  Audio registers, 0xFF10 - 0xFF26, plus wave RAM at 0xFF30 - 0xFF3F.
+
+  Only the register file is modelled: reads return the documented
+  unused-bit patterns, writes are dropped while the APU is powered off
+  (NR52 bit 7) and powering off clears every register. Triggering a
+  channel whose DAC is on sets its status bit in NR52.
+*/
+
+#[derive(Debug)]
+pub struct ApuContext {
+    pub regs: [u8; 0x17],
+    pub wave_ram: [u8; 0x10],
+    pub power: bool,
+    pub channels_on: u8,
+}
+
+static mut CTX: ApuContext = ApuContext {
+    regs: [0; 0x17],
+    wave_ram: [0; 0x10],
+    power: false,
+    channels_on: 0,
+};
+
+// Bits that always read back as 1 for 0xFF10 - 0xFF26
+static READ_MASKS: [u8; 0x17] = [
+    0x80, 0x3F, 0x00, 0xFF, 0xBF, // NR10 - NR14
+    0xFF, 0x3F, 0x00, 0xFF, 0xBF, // unused, NR21 - NR24
+    0x7F, 0xFF, 0x9F, 0xFF, 0xBF, // NR30 - NR34
+    0xFF, 0xFF, 0x00, 0x00, 0xBF, // unused, NR41 - NR44
+    0x00, 0x00, 0x70, // NR50 - NR52
+];
+
+/*
+  The APU is off at power-on. The boot ROM powers it on and plays its
+  chime on channel 1, leaving NR11 0xBF, NR12 0xF3, NR50 0x77, NR51 0xF3
+  and channel 1 still flagged in NR52, except on SGB which stays silent.
+*/
+pub unsafe fn apu_init() {
+    CTX.regs = [0; 0x17];
+    CTX.wave_ram = [0; 0x10];
+
+    if boot_rom_mapped() {
+        CTX.power = false;
+        CTX.channels_on = 0;
+        return;
+    }
+
+    CTX.regs[0x01] = 0x80;
+    CTX.regs[0x02] = 0xF3;
+    CTX.regs[0x14] = 0x77;
+    CTX.regs[0x15] = 0xF3;
+    CTX.power = true;
+    CTX.channels_on = if emu_model().is_sgb() { 0 } else { 1 };
+}
+
+pub fn apu_tick() {}
+
+unsafe fn dac_enabled(channel: usize) -> bool {
+    match channel {
+        // NR30 bit 7 for the wave channel, otherwise the envelope's upper 5 bits
+        2 => bit(CTX.regs[0x0A], 7),
+        _ => CTX.regs[channel * 5 + 2] & 0xF8 != 0,
+    }
+}
+
+pub unsafe fn apu_read(address: u16) -> u8 {
+    match address {
+        0xFF30..=0xFF3F => CTX.wave_ram[(address - 0xFF30) as usize],
+        0xFF26 => 0x70 | ((CTX.power as u8) << 7) | CTX.channels_on,
+        0xFF10..=0xFF25 => {
+            let index = (address - 0xFF10) as usize;
+            CTX.regs[index] | READ_MASKS[index]
+        }
+        _ => 0xFF,
+    }
+}
+
+pub unsafe fn apu_write(address: u16, value: u8) {
+    match address {
+        0xFF30..=0xFF3F => CTX.wave_ram[(address - 0xFF30) as usize] = value,
+        0xFF26 => {
+            let power = bit(value, 7);
+
+            if !power {
+                CTX.regs = [0; 0x17];
+                CTX.channels_on = 0;
+            }
+
+            CTX.power = power;
+        }
+        0xFF10..=0xFF25 if CTX.power => {
+            let index = (address - 0xFF10) as usize;
+            CTX.regs[index] = value;
+
+            let channel = index / 5;
+            if channel > 3 {
+                return;
+            }
+
+            if !dac_enabled(channel) {
+                CTX.channels_on &= !(1 << channel);
+            } else if index % 5 == 4 && bit(value, 7) {
+                //NRx4 trigger
+                CTX.channels_on |= 1 << channel;
+            }
+        }
+        _ => (),
+    }
+}
@@ -0,0 +1,94 @@
+use flate2::read::GzDecoder;
+use std::error::Error;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+/*
+  ROMs are read either as plain images or out of a compressed file, told
+  apart by their magic bytes:
+
+  50 4B 03 04 : zip, the chosen entry or else the first .gb/.gbc entry
+  1F 8B       : gzip, a single compressed ROM
+
+  The name returned is the ROM's own file name, taken from inside the
+  archive when there is one, so save files follow the ROM rather than
+  the archive.
+*/
+
+#[derive(Debug)]
+pub struct RomFile {
+    pub data: Vec<u8>,
+    pub name: String,
+}
+
+fn file_name(path: &str) -> String {
+    match Path::new(path).file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => path.to_string(),
+    }
+}
+
+fn is_rom_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.ends_with(".gb") || name.ends_with(".gbc")
+}
+
+fn zip_read(data: Vec<u8>, entry: Option<&str>) -> Result<RomFile, Box<dyn Error>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))?;
+
+    let index = match entry {
+        Some(entry) => archive
+            .index_for_name(entry)
+            .ok_or_else(|| format!("no entry named {} in the archive", entry))?,
+        None => (0..archive.len())
+            .find(|i| match archive.name_for_index(*i) {
+                Some(Ok(name)) => is_rom_name(&name),
+                _ => false,
+            })
+            .ok_or("no .gb or .gbc entry in the archive")?,
+    };
+
+    let mut file = archive.by_index(index)?;
+    let name = file_name(&file.name()?);
+
+    let mut rom = Vec::new();
+    file.read_to_end(&mut rom)?;
+
+    Ok(RomFile { data: rom, name })
+}
+
+// The gzip header may carry the original file name, otherwise .gz is dropped from the path
+fn gz_read(data: Vec<u8>, path: &str) -> Result<RomFile, Box<dyn Error>> {
+    let mut decoder = GzDecoder::new(&data[..]);
+    let mut rom = Vec::new();
+    decoder.read_to_end(&mut rom)?;
+
+    let name = match decoder.header().and_then(|h| h.filename()) {
+        Some(name) => file_name(&String::from_utf8_lossy(name)),
+        None => {
+            let name = file_name(path);
+            match name.strip_suffix(".gz") {
+                Some(stem) => stem.to_string(),
+                None => name,
+            }
+        }
+    };
+
+    Ok(RomFile { data: rom, name })
+}
+
+pub fn rom_file_read(path: &str, zip_entry: Option<&str>) -> Result<RomFile, Box<dyn Error>> {
+    let data = fs::read(path)?;
+
+    if data.starts_with(&[0x50, 0x4B, 0x03, 0x04]) {
+        zip_read(data, zip_entry)
+    } else if data.starts_with(&[0x1F, 0x8B]) {
+        gz_read(data, path)
+    } else {
+        Ok(RomFile {
+            data,
+            name: file_name(path),
+        })
+    }
+}
@@ -0,0 +1,112 @@
+use super::emu::emu_set_cgb_mode;
+use super::model::HardwareModel;
+use std::error::Error;
+use std::fs;
+
+/*
+  Boot ROM, mapped over the cartridge until 0xFF50 is written with bit 0 set:
+
+  DMG/MGB/SGB : 256 bytes at 0x0000 - 0x00FF
+  CGB         : 2304 bytes, 0x0000 - 0x00FF and 0x0200 - 0x08FF, the cartridge
+                header at 0x0100 - 0x01FF stays visible
+
+  0xFF4C KEY0 is only writable while a CGB boot ROM is mapped. The boot ROM
+  copies the cartridge's CGB flag there, bit 2 set drops the console into
+  DMG compatibility mode once the boot ROM unmaps itself.
+*/
+
+#[derive(Debug)]
+pub struct BootContext {
+    pub rom: Vec<u8>,
+    pub mapped: bool,
+    pub cgb: bool,
+    key0: u8,
+}
+
+static mut CTX: BootContext = BootContext {
+    rom: Vec::new(),
+    mapped: false,
+    cgb: false,
+    key0: 0,
+};
+
+// With a model given the image has to be that model's boot ROM, otherwise the size picks DMG or CGB
+pub unsafe fn boot_load(file: &str, model: Option<HardwareModel>) -> Result<(), Box<dyn Error>> {
+    let rom = fs::read(file)?;
+
+    if let Some(model) = model {
+        if rom.len() != model.boot_rom_size() {
+            return Err(format!(
+                "boot ROM is {} bytes, the {:?} model expects {}",
+                rom.len(),
+                model,
+                model.boot_rom_size()
+            )
+            .into());
+        }
+    }
+
+    CTX.cgb = match rom.len() {
+        0x100 => false,
+        0x900 => true,
+        size => {
+            return Err(format!(
+                "boot ROM is {} bytes, expected 256 (DMG/MGB/SGB) or 2304 (CGB)",
+                size
+            )
+            .into())
+        }
+    };
+
+    CTX.rom = rom;
+    CTX.mapped = true;
+    CTX.key0 = 0;
+
+    println!(
+        "Boot ROM: {} ({})",
+        file,
+        if CTX.cgb { "CGB" } else { "DMG" }
+    );
+
+    Ok(())
+}
+
+// True until the boot ROM unmaps itself, the CPU starts at 0x0000 instead of 0x0100
+pub unsafe fn boot_rom_mapped() -> bool {
+    CTX.mapped
+}
+
+pub unsafe fn boot_rom_cgb() -> bool {
+    CTX.cgb
+}
+
+pub unsafe fn boot_read(address: u16) -> Option<u8> {
+    if !CTX.mapped {
+        return None;
+    }
+
+    match address {
+        0x0000..=0x00FF => Some(CTX.rom[address as usize]),
+        0x0200..=0x08FF if CTX.cgb => Some(CTX.rom[address as usize]),
+        _ => None,
+    }
+}
+
+pub unsafe fn boot_key0_write(value: u8) {
+    if CTX.mapped && CTX.cgb {
+        CTX.key0 = value;
+    }
+}
+
+// 0xFF50, the boot ROM can never be mapped back in
+pub unsafe fn boot_write(value: u8) {
+    if !CTX.mapped || value & 1 == 0 {
+        return;
+    }
+
+    CTX.mapped = false;
+
+    if CTX.cgb && CTX.key0 & 0x04 != 0 {
+        emu_set_cgb_mode(false);
+    }
+}
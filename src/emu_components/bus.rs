@@ -1,5 +1,10 @@
+use super::boot::boot_read;
 use super::cart::{cart_read, cart_write};
 use super::cpu::{cpu_get_ie_register, cpu_set_ie_register};
+use super::dma::dma_transferring;
+use super::emu::emu_model;
+use super::io::{io_read, io_write};
+use super::ppu::{oam_accessible, ppu_oam_read, ppu_oam_write, ppu_vram_read, ppu_vram_write};
 use super::ram::{hram_read, hram_write, wram_read, wram_write};
 // 0x0000 - 0x3FFF : ROM Bank 0
 // 0x4000 - 0x7FFF : ROM Bank 1 - Switchable
@@ -9,20 +14,52 @@ use super::ram::{hram_read, hram_write, wram_read, wram_write};
 // 0xA000 - 0xBFFF : Cartridge RAM
 // 0xC000 - 0xCFFF : RAM Bank 0
 // 0xD000 - 0xDFFF : RAM Bank 1-7 - switchable - Color only
-// 0xE000 - 0xFDFF : Reserved - Echo RAM
+// 0xE000 - 0xFDFF : Reserved - Echo RAM, mirrors 0xC000 - 0xDDFF
 // 0xFE00 - 0xFE9F : Object Attribute Memory
 // 0xFEA0 - 0xFEFF : Reserved - Unusable
+//                   reads 0xFF while OAM is blocked, otherwise 0x00 on
+//                   DMG/MGB/SGB and the low byte's high nibble twice on CGB
 // 0xFF00 - 0xFF7F : I/O Registers
 // 0xFF80 - 0xFFFE : Zero Page
+//
+// While OAM DMA is running the CPU only sees HRAM and the registers,
+// everything else reads 0xFF and ignores writes.
+
+unsafe fn unusable_read(address: u16) -> u8 {
+    if !oam_accessible() {
+        0xFF
+    } else if emu_model().is_cgb() {
+        let nibble = (address >> 4) as u8 & 0x0F;
+        (nibble << 4) | nibble
+    } else {
+        0x00
+    }
+}
+
+fn dma_blocked(address: u16) -> bool {
+    address < 0xFF00
+}
 
 pub unsafe fn bus_read(address: u16) -> u8 {
+    if dma_transferring() && dma_blocked(address) {
+        return 0xFF;
+    }
+
+    bus_read_direct(address)
+}
+
+// Bus read without the DMA restriction, used as the DMA source
+pub unsafe fn bus_read_direct(address: u16) -> u8 {
     if address < 0x8000 {
-        //ROM Data
+        //ROM Data, the boot ROM overlays it until it unmaps itself
+        if let Some(value) = boot_read(address) {
+            return value;
+        }
+
         return cart_read(address);
     } else if address < 0xA000 {
         //Char/Map Data
-        //TODO
-        panic!("UNSUPPORTED  write read ({:04X})", address);
+        return ppu_vram_read(address);
     } else if address < 0xC000 {
         //Cartridge RAM
         return cart_read(address);
@@ -31,18 +68,16 @@ pub unsafe fn bus_read(address: u16) -> u8 {
         return wram_read(address);
     } else if address < 0xFE00 {
         //reserved echo ram...
-        return 0;
+        return wram_read(address - 0x2000);
     } else if address < 0xFEA0 {
         //OAM
-        //TODO
-        panic!("UNSUPPORTED  write read ({:04X})", address);
+        return ppu_oam_read(address);
     } else if address < 0xFF00 {
         //reserved unusable...
-        return 0;
+        return unusable_read(address);
     } else if address < 0xFF80 {
         //IO Registers...
-        //TODO
-        panic!("UNSUPPORTED  write read ({:04X})", address);
+        return io_read(address);
     } else if address == 0xFFFF {
         //CPU ENABLE REGISTER...
         return cpu_get_ie_register();
@@ -53,33 +88,34 @@ pub unsafe fn bus_read(address: u16) -> u8 {
 }
 
 pub unsafe fn bus_write(address: u16, value: u8) {
+    if dma_transferring() && dma_blocked(address) {
+        return;
+    }
+
     if address < 0x8000 {
         //ROM Data
         cart_write(address, value);
     } else if address < 0xA000 {
         //Char/Map Data
-        //TODO
-        println!("UNSUPPORTED  write read ({:04X})", address);
-        // panic!("UNSUPPORTED  write read ({:04X})", address);
+        ppu_vram_write(address, value);
     } else if address < 0xC000 {
+        //Cartridge RAM
+        cart_write(address, value);
+    } else if address < 0xE000 {
         //WRAM
         wram_write(address, value);
     } else if address < 0xFE00 {
         //reserved echo ram
-        return;
+        wram_write(address - 0x2000, value);
     } else if address < 0xFEA0 {
         //OAM
-        //TODO
-        println!("UNSUPPORTED  write read ({:04X})", address);
-        // panic!("UNSUPPORTED  write read ({:04X})", address);
+        ppu_oam_write(address, value);
     } else if address < 0xFF00 {
         //unusable reserved
         return;
     } else if address < 0xFF80 {
         //IO Registers...
-        //TODO
-        println!("UNSUPPORTED  write read ({:04X})", address);
-        // panic!("UNSUPPORTED  write read ({:04X})", address);
+        io_write(address, value);
     } else if address == 0xFFFF {
         //CPU SET ENABLE REGISTER
         cpu_set_ie_register(value);
@@ -90,11 +126,11 @@ pub unsafe fn bus_write(address: u16, value: u8) {
 
 pub unsafe fn bus_read16(address: u16) -> u16 {
     let lo = bus_read(address) as u16;
-    let hi = bus_read(address + 1) as u16;
+    let hi = bus_read(address.wrapping_add(1)) as u16;
     lo | (hi << 8)
 }
 
 pub unsafe fn bus_write16(address: u16, value: u16) {
-    bus_write(address + 1, (value >> 8) as u8);
+    bus_write(address.wrapping_add(1), (value >> 8) as u8);
     bus_write(address, value as u8);
 }
@@ -1,39 +1,146 @@
-use super::common::convert_to_u16;
+use super::archive::rom_file_read;
+use super::cart_header::{ram_size_bytes, CgbSupport, RomHeader};
+use super::patch::patch_apply;
 use std::error::Error;
-use std::fs::File;
-use std::io::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MbcType {
+    MBC_NONE,
+    MBC1,
+    MBC1M,
+    MBC2,
+    MBC3,
+    MBC5,
+}
+
+/*
+  MBC1 registers, selected by the write address:
+
+  0x0000 - 0x1FFF : RAM enable, 0x0A in the low nibble enables
+  0x2000 - 0x3FFF : BANK1, 5-bit ROM bank, 0 is treated as 1
+  0x4000 - 0x5FFF : BANK2, 2-bit RAM bank or upper ROM bank bits
+  0x6000 - 0x7FFF : banking mode
+
+  0x4000 - 0x7FFF reads bank (BANK2 << 5) | BANK1. In mode 1 BANK2 also
+  applies to 0x0000 - 0x3FFF and to the RAM bank. MBC1M multicarts wire
+  only 4 bits of BANK1, so BANK2 is shifted by 4 instead.
+*/
 #[derive(Debug)]
-pub struct RomHeader {
-    entry: [u8; 4],
-    logo: [u8; 48],
-    title: [u8; 16],
-    manufacturer_code: [u8; 4],
-    cgb_flag: u8,
-    new_lic_code: u16,
-    sgb_flag: u8,
-    c_type: u8,
-    rom_size: u8,
-    ram_size: u8,
-    dest_code: u8,
-    lic_code: u8,
-    version: u8,
-    checksum: u8,
-    global_checksum: u16,
+pub struct MbcRegisters {
+    pub ram_enabled: bool,
+    pub bank1: u8,
+    pub bank2: u8,
+    pub mode: u8,
+    pub rom_bank_high: u8,
+}
+
+/*
+  MBC2, only 0x0000 - 0x3FFF takes register writes and address bit 8 picks the register:
+
+  bit 8 clear : RAM enable, 0x0A in the low nibble enables
+  bit 8 set   : BANK1, 4-bit ROM bank, 0 is treated as 1
+
+  The built-in RAM is 512 half-bytes mirrored across 0xA000 - 0xBFFF,
+  only the low nibble is stored and the upper nibble reads as 1s.
+*/
+
+/*
+  MBC3 reuses the same registers:
+
+  0x0000 - 0x1FFF : RAM and RTC enable
+  0x2000 - 0x3FFF : BANK1, 7-bit ROM bank, 0 is treated as 1 (MBC30 is not supported)
+  0x4000 - 0x5FFF : BANK2, RAM bank 0x00-0x07 or RTC register 0x08-0x0C
+  0x6000 - 0x7FFF : latch clock data, writing 0x00 then 0x01 latches the RTC
+
+  RTC registers:
+
+  0x08 RTC S  : seconds 0-59
+  0x09 RTC M  : minutes 0-59
+  0x0A RTC H  : hours 0-23
+  0x0B RTC DL : lower 8 bits of the day counter
+  0x0C RTC DH : bit 0 day counter bit 8, bit 6 halt, bit 7 day counter carry
+*/
+/*
+  MBC5:
+
+  0x0000 - 0x1FFF : RAM enable, only 0x0A enables
+  0x2000 - 0x2FFF : BANK1, low 8 bits of the ROM bank, bank 0 is allowed
+  0x3000 - 0x3FFF : ROM bank bit 8
+  0x4000 - 0x5FFF : BANK2, RAM bank 0x00-0x0F, bit 3 drives the motor on RUMBLE carts
+*/
+pub type RumbleCallback = fn(bool);
+
+#[derive(Debug, Clone, Copy)]
+pub struct RtcContext {
+    // S, M, H, DL, DH
+    pub regs: [u8; 5],
+    pub latched: [u8; 5],
+    // Host time in milliseconds the live registers were last brought up to date
+    pub last_time: u64,
+}
+
+static RTC_MASKS: [u8; 5] = [0x3F, 0x3F, 0x1F, 0xFF, 0xC1];
+
+/*
+  Battery saves are the raw external RAM written to <rom>.sav. MBC3 carts
+  with a timer append the BGB/VBA-M footer:
+
+  5 x u32 LE : S, M, H, DL, DH
+  5 x u32 LE : latched S, M, H, DL, DH
+  u64 LE     : UNIX timestamp in seconds (older saves use a u32, 44 bytes in total)
+
+  The file is written once no RAM write has happened for SAVE_DEBOUNCE_MS,
+  or after SAVE_MAX_DELAY_MS for games that keep writing, and on exit.
+  Only writes that change the RAM or the RTC registers count, writes while
+  RAM is disabled are dropped by the mapper before that.
+*/
+const RTC_FOOTER_LEN: usize = 48;
+const SAVE_DEBOUNCE_MS: u64 = 1000;
+const SAVE_MAX_DELAY_MS: u64 = 10000;
+
+#[derive(Debug, Default)]
+pub struct CartOptions {
+    // Accept any header that is not truncated, only warning about problems
+    pub lenient: bool,
+    // Zip entry to load instead of the first .gb/.gbc one
+    pub zip_entry: Option<String>,
+    // IPS/UPS/BPS patches applied in order, <rom>.ips/.ups/.bps is looked for when empty
+    pub patches: Vec<String>,
 }
 
 #[derive(Debug)]
 pub struct CartContext {
     pub filename: String,
+    // ROM file name, the entry name when loaded from an archive
+    pub rom_name: String,
     pub rom_size: u64,
     pub rom_data: Vec<u8>,
+    pub ram_data: Vec<u8>,
     pub header: RomHeader,
+    pub mbc: MbcType,
+    pub regs: MbcRegisters,
+    pub has_rtc: bool,
+    pub rtc: RtcContext,
+    pub has_rumble: bool,
+    pub rumble: bool,
+    pub rumble_callback: Option<RumbleCallback>,
+    pub has_battery: bool,
+    pub save_file: String,
+    // Host times in milliseconds of the first and the last RAM write since the last save
+    ram_dirty_since: Option<u64>,
+    ram_last_write: u64,
 }
 
 static mut CTX: CartContext = CartContext {
     filename: String::new(),
+    rom_name: String::new(),
     rom_size: 0,
     rom_data: Vec::new(),
+    ram_data: Vec::new(),
     header: RomHeader {
         entry: [0; 4],
         logo: [0; 48],
@@ -51,196 +158,627 @@ static mut CTX: CartContext = CartContext {
         checksum: 0,
         global_checksum: 0,
     },
+    mbc: MbcType::MBC_NONE,
+    regs: MbcRegisters {
+        ram_enabled: false,
+        bank1: 1,
+        bank2: 0,
+        mode: 0,
+        rom_bank_high: 0,
+    },
+    has_rtc: false,
+    rtc: RtcContext {
+        regs: [0; 5],
+        latched: [0; 5],
+        last_time: 0,
+    },
+    has_rumble: false,
+    rumble: false,
+    rumble_callback: None,
+    has_battery: false,
+    save_file: String::new(),
+    ram_dirty_since: None,
+    ram_last_write: 0,
 };
 
-static ROM_TYPES: [&str; 35] = [
-    "ROM ONLY",
-    "MBC1",
-    "MBC1+RAM",
-    "MBC1+RAM+BATTERY",
-    "0x04 ???",
-    "MBC2",
-    "MBC2+BATTERY",
-    "0x07 ???",
-    "ROM+RAM 1",
-    "ROM+RAM+BATTERY 1",
-    "0x0A ???",
-    "MMM01",
-    "MMM01+RAM",
-    "MMM01+RAM+BATTERY",
-    "0x0E ???",
-    "MBC3+TIMER+BATTERY",
-    "MBC3+TIMER+RAM+BATTERY 2",
-    "MBC3",
-    "MBC3+RAM 2",
-    "MBC3+RAM+BATTERY 2",
-    "0x14 ???",
-    "0x15 ???",
-    "0x16 ???",
-    "0x17 ???",
-    "0x18 ???",
-    "MBC5",
-    "MBC5+RAM",
-    "MBC5+RAM+BATTERY",
-    "MBC5+RUMBLE",
-    "MBC5+RUMBLE+RAM",
-    "MBC5+RUMBLE+RAM+BATTERY",
-    "0x1F ???",
-    "MBC6",
-    "0x21 ???",
-    "MBC7+SENSOR+RUMBLE+RAM+BATTERY",
-];
-
-fn lic_code(code: u8) -> String {
-    let lic_code = vec![
-        (0x00, "None"),
-        (0x01, "Nintendo R&D1"),
-        (0x08, "Capcom"),
-        (0x13, "Electronic Arts"),
-        (0x18, "Hudson Soft"),
-        (0x19, "b-ai"),
-        (0x20, "kss"),
-        (0x22, "pow"),
-        (0x24, "PCM Complete"),
-        (0x25, "san-x"),
-        (0x28, "Kemco Japan"),
-        (0x29, "seta"),
-        (0x30, "Viacom"),
-        (0x31, "Nintendo"),
-        (0x32, "Bandai"),
-        (0x33, "Ocean/Acclaim"),
-        (0x34, "Konami"),
-        (0x35, "Hector"),
-        (0x37, "Taito"),
-        (0x38, "Hudson"),
-        (0x39, "Banpresto"),
-        (0x41, "Ubi Soft"),
-        (0x42, "Atlus"),
-        (0x44, "Malibu"),
-        (0x46, "angel"),
-        (0x47, "Bullet-Proof"),
-        (0x49, "irem"),
-        (0x50, "Absolute"),
-        (0x51, "Acclaim"),
-        (0x52, "Activision"),
-        (0x53, "American sammy"),
-        (0x54, "Konami"),
-        (0x55, "Hi tech entertainment"),
-        (0x56, "LJN"),
-        (0x57, "Matchbox"),
-        (0x58, "Mattel"),
-        (0x59, "Milton Bradley"),
-        (0x60, "Titus"),
-        (0x61, "Virgin"),
-        (0x64, "LucasArts"),
-        (0x67, "Ocean"),
-        (0x69, "Electronic Arts"),
-        (0x70, "Infogrames"),
-        (0x71, "Interplay"),
-        (0x72, "Broderbund"),
-        (0x73, "sculptured"),
-        (0x75, "sci"),
-        (0x78, "THQ"),
-        (0x79, "Accolade"),
-        (0x80, "misawa"),
-        (0x83, "lozc"),
-        (0x86, "Tokuma Shoten Intermedia"),
-        (0x87, "Tsukuda Original"),
-        (0x91, "Chunsoft"),
-        (0x92, "Video system"),
-        (0x93, "Ocean/Acclaim"),
-        (0x95, "Varie"),
-        (0x96, "Yonezawa/s’pal"),
-        (0x97, "Kaneko"),
-        (0x99, "Pack in soft"),
-        (0xA4, "Konami (Yu-Gi-Oh!)"),
-    ];
-
-    match lic_code.iter().find(|(lic_code, _)| lic_code == &code) {
-        Some((_, string)) => string.to_string(),
-        None => "UNKNOWN".to_string(),
-    }
-}
-
-unsafe fn populate_header() {
-    CTX.header
-        .entry
-        .copy_from_slice(&CTX.rom_data[0x100..=0x103]);
-    CTX.header
-        .logo
-        .copy_from_slice(&CTX.rom_data[0x104..=0x133]);
-    CTX.header
-        .title
-        .copy_from_slice(&CTX.rom_data[0x134..=0x143]);
-    CTX.header
-        .manufacturer_code
-        .copy_from_slice(&CTX.rom_data[0x13F..=0x142]);
-    CTX.header.cgb_flag = CTX.rom_data[0x143];
-    CTX.header.new_lic_code = convert_to_u16(CTX.rom_data[0x144], CTX.rom_data[0x145]);
-    CTX.header.sgb_flag = CTX.rom_data[0x146];
-    CTX.header.c_type = CTX.rom_data[0x147];
-    CTX.header.rom_size = CTX.rom_data[0x148];
-    CTX.header.ram_size = CTX.rom_data[0x149];
-    CTX.header.dest_code = CTX.rom_data[0x14A];
-    CTX.header.lic_code = CTX.rom_data[0x14B];
-    CTX.header.version = CTX.rom_data[0x14C];
-    CTX.header.checksum = CTX.rom_data[0x14D];
-    CTX.header.global_checksum = convert_to_u16(CTX.rom_data[0x14E], CTX.rom_data[0x14F])
-}
-
-pub unsafe fn cart_load(cart: String) -> Result<(), Box<dyn Error>> {
+// First of <rom>.ips, <rom>.ups and <rom>.bps found next to the ROM file
+unsafe fn find_patch() -> Option<String> {
+    let rom = Path::new(&CTX.filename).with_file_name(&CTX.rom_name);
+
+    ["ips", "ups", "bps"]
+        .iter()
+        .map(|ext| rom.with_extension(ext))
+        .find(|path| path.is_file())
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
+pub unsafe fn cart_load(cart: String, options: &CartOptions) -> Result<(), Box<dyn Error>> {
     CTX.filename = cart.to_owned();
 
-    let mut fp = File::open(&CTX.filename)?;
+    let rom_file = rom_file_read(&CTX.filename, options.zip_entry.as_deref())?;
+
+    println!("Opened: {} ({})", &CTX.filename, &rom_file.name);
 
-    println!("Opened: {}", &CTX.filename);
+    let mut rom = rom_file.data;
+    CTX.rom_name = rom_file.name;
 
-    let rom_size = fp.metadata()?.len();
-    CTX.rom_size = rom_size;
+    let patches = if options.patches.is_empty() {
+        find_patch().into_iter().collect()
+    } else {
+        options.patches.clone()
+    };
 
-    let mut rom_in_memory = Vec::new();
-    fp.read_to_end(&mut rom_in_memory)?;
+    for patch in patches {
+        let data = fs::read(&patch).map_err(|e| format!("{}: {}", patch, e))?;
+        rom = patch_apply(&rom, &data).map_err(|e| format!("{}: {}", patch, e))?;
 
-    CTX.rom_data = rom_in_memory.to_owned();
+        println!("Patched: {}", patch);
+    }
+
+    CTX.rom_size = rom.len() as u64;
+    CTX.header = RomHeader::parse(&rom, options.lenient)?;
+    CTX.rom_data = rom;
     println!("Cartridge Loaded:");
 
-    populate_header();
-    println!(
-        "\t Title    : {}",
-        String::from_utf8_lossy(&CTX.header.title)
-    );
-    println!(
-        "\t Type     : {} ({})",
-        CTX.header.c_type, ROM_TYPES[CTX.header.c_type as usize]
-    );
-    println!("\t ROM Size : {} KB", 32 << CTX.header.rom_size);
-    println!("\t RAM Size : {}", CTX.header.ram_size);
-    println!(
-        "\t LIC Code : {} {}",
-        CTX.header.lic_code,
-        lic_code(CTX.header.lic_code)
-    );
-    println!("\t ROM Vers : {}", CTX.header.version);
+    let mut summary = String::new();
+    CTX.header.write_summary(&mut summary, &CTX.rom_data)?;
+    print!("{}", summary);
 
-    let mut x: u16 = 0;
-    for i in 0x134..=0x14C {
-        x = x.wrapping_sub(CTX.rom_data[i] as u16).wrapping_sub(1);
+    for e in CTX.header.check(&CTX.rom_data) {
+        println!("\t Warning  : {}", e);
     }
 
-    println!(
-        "\t Checksum : {:02X} ({})",
-        CTX.header.checksum,
-        if (x & 0xFF) > 0 { "PASSED" } else { "FAILED" }
+    CTX.mbc = mbc_type(CTX.header.c_type);
+    CTX.ram_data = if CTX.mbc == MbcType::MBC2 {
+        vec![0; 0x200]
+    } else {
+        vec![0; ram_size_bytes(CTX.header.ram_size)]
+    };
+    CTX.regs = MbcRegisters {
+        ram_enabled: false,
+        bank1: 1,
+        bank2: 0,
+        mode: 0,
+        rom_bank_high: 0,
+    };
+    CTX.has_rumble = matches!(CTX.header.c_type, 0x1C..=0x1E);
+    CTX.rumble = false;
+    CTX.has_rtc = matches!(CTX.header.c_type, 0x0F | 0x10);
+    CTX.rtc = RtcContext {
+        regs: [0; 5],
+        latched: [0; 5],
+        last_time: host_time_ms(),
+    };
+    // HuC1 (0xFF) runs as ROM ONLY and never maps its RAM, so it gets no save file
+    CTX.has_battery = matches!(
+        CTX.header.c_type,
+        0x03 | 0x06 | 0x09 | 0x0D | 0x0F | 0x10 | 0x13 | 0x1B | 0x1E | 0x22
     );
+    CTX.save_file = Path::new(&CTX.filename)
+        .with_file_name(&CTX.rom_name)
+        .with_extension("sav")
+        .to_string_lossy()
+        .into_owned();
+    CTX.ram_dirty_since = None;
+
+    if CTX.has_battery {
+        cart_battery_load();
+    }
+
+    Ok(())
+}
+
+pub unsafe fn cart_header() -> &'static RomHeader {
+    &CTX.header
+}
+
+pub unsafe fn cart_is_cgb() -> bool {
+    CTX.header.cgb_support() != CgbSupport::CGB_NONE
+}
+
+unsafe fn cart_battery_load() {
+    let data = match fs::read(&CTX.save_file) {
+        Ok(data) => data,
+        Err(_) => return,
+    };
+
+    println!("\t Save     : {}", &CTX.save_file);
+
+    let ram_len = CTX.ram_data.len().min(data.len());
+    CTX.ram_data[..ram_len].copy_from_slice(&data[..ram_len]);
+
+    let footer = &data[ram_len..];
+    if !CTX.has_rtc || footer.len() < RTC_FOOTER_LEN - 4 {
+        return;
+    }
+
+    let word = |i: usize| {
+        u32::from_le_bytes([
+            footer[i * 4],
+            footer[i * 4 + 1],
+            footer[i * 4 + 2],
+            footer[i * 4 + 3],
+        ])
+    };
+
+    let mut rtc = RtcContext {
+        regs: [0; 5],
+        latched: [0; 5],
+        last_time: word(10) as u64,
+    };
+
+    for (i, mask) in RTC_MASKS.iter().enumerate() {
+        rtc.regs[i] = word(i) as u8 & mask;
+        rtc.latched[i] = word(i + 5) as u8 & mask;
+    }
+
+    if footer.len() >= RTC_FOOTER_LEN {
+        rtc.last_time |= (word(11) as u64) << 32;
+    }
+
+    rtc.last_time *= 1000;
+    cart_rtc_restore(rtc);
+}
+
+// Writes the external RAM, plus the RTC footer on timer carts, to the save file
+pub unsafe fn cart_battery_save() -> Result<(), Box<dyn Error>> {
+    if !CTX.has_battery {
+        return Ok(());
+    }
+
+    let mut data = CTX.ram_data.clone();
+
+    if CTX.has_rtc {
+        let rtc = cart_rtc_state();
+
+        for value in rtc.regs.iter().chain(rtc.latched.iter()) {
+            data.extend_from_slice(&(*value as u32).to_le_bytes());
+        }
+
+        data.extend_from_slice(&(rtc.last_time / 1000).to_le_bytes());
+    }
+
+    fs::write(&CTX.save_file, data)?;
+    CTX.ram_dirty_since = None;
 
     Ok(())
 }
 
+// Called regularly by the emulator loop, saves once RAM writes have settled
+pub unsafe fn cart_battery_tick() {
+    let dirty_since = match CTX.ram_dirty_since {
+        Some(time) => time,
+        None => return,
+    };
+
+    let now = host_time_ms();
+    if now.saturating_sub(CTX.ram_last_write) < SAVE_DEBOUNCE_MS
+        && now.saturating_sub(dirty_since) < SAVE_MAX_DELAY_MS
+    {
+        return;
+    }
+
+    if let Err(e) = cart_battery_save() {
+        println!("Failed to write save {}: {}", &CTX.save_file, e);
+        CTX.ram_dirty_since = None;
+    }
+}
+
+unsafe fn mark_ram_dirty() {
+    if !CTX.has_battery {
+        return;
+    }
+
+    let now = host_time_ms();
+
+    CTX.ram_last_write = now;
+    if CTX.ram_dirty_since.is_none() {
+        CTX.ram_dirty_since = Some(now);
+    }
+}
+
+// Only writes that change the RAM count towards the next save
+unsafe fn ram_write(offset: usize, value: u8) {
+    if CTX.ram_data[offset] != value {
+        CTX.ram_data[offset] = value;
+        mark_ram_dirty();
+    }
+}
+
+unsafe fn mbc_type(c_type: u8) -> MbcType {
+    match c_type {
+        0x00 | 0x08 | 0x09 => MbcType::MBC_NONE,
+        0x01..=0x03 if is_mbc1_multicart() => MbcType::MBC1M,
+        0x01..=0x03 => MbcType::MBC1,
+        0x05 | 0x06 => MbcType::MBC2,
+        0x0F..=0x13 => MbcType::MBC3,
+        0x19..=0x1E => MbcType::MBC5,
+        _ => {
+            println!("Unsupported mapper {:02X}, running as ROM ONLY", c_type);
+            MbcType::MBC_NONE
+        }
+    }
+}
+
+// MBC1M carts are 1 MiB and carry a second Nintendo logo in the header of bank 0x10
+unsafe fn is_mbc1_multicart() -> bool {
+    let header = 0x10 * 0x4000 + 0x104;
+    CTX.rom_data.len() == 0x100000 && CTX.rom_data[header..header + 48] == CTX.header.logo
+}
+
+unsafe fn rom_bank_read(bank: usize, address: u16) -> u8 {
+    let banks = (CTX.rom_data.len() / 0x4000).max(1);
+    let offset = (bank % banks) * 0x4000 + (address as usize & 0x3FFF);
+
+    match CTX.rom_data.get(offset) {
+        Some(value) => *value,
+        None => 0xFF,
+    }
+}
+
+unsafe fn ram_offset(bank: usize, address: u16) -> Option<usize> {
+    if CTX.ram_data.is_empty() {
+        return None;
+    }
+
+    let offset = bank * 0x2000 + (address - 0xA000) as usize;
+    Some(offset % CTX.ram_data.len())
+}
+
+unsafe fn mbc1_bank2_shift() -> u8 {
+    if CTX.mbc == MbcType::MBC1M {
+        4
+    } else {
+        5
+    }
+}
+
+unsafe fn mbc1_read(address: u16) -> u8 {
+    let shift = mbc1_bank2_shift();
+    let bank1 = if CTX.mbc == MbcType::MBC1M {
+        CTX.regs.bank1 & 0x0F
+    } else {
+        CTX.regs.bank1
+    };
+
+    match address {
+        0x0000..=0x3FFF => {
+            let bank = if CTX.regs.mode == 1 {
+                (CTX.regs.bank2 as usize) << shift
+            } else {
+                0
+            };
+            rom_bank_read(bank, address)
+        }
+        0x4000..=0x7FFF => {
+            let bank = ((CTX.regs.bank2 as usize) << shift) | bank1 as usize;
+            rom_bank_read(bank, address)
+        }
+        _ => {
+            if !CTX.regs.ram_enabled {
+                return 0xFF;
+            }
+
+            let bank = if CTX.regs.mode == 1 {
+                CTX.regs.bank2 as usize
+            } else {
+                0
+            };
+
+            match ram_offset(bank, address) {
+                Some(offset) => CTX.ram_data[offset],
+                None => 0xFF,
+            }
+        }
+    }
+}
+
+unsafe fn mbc1_write(address: u16, value: u8) {
+    match address {
+        0x0000..=0x1FFF => CTX.regs.ram_enabled = value & 0x0F == 0x0A,
+        0x2000..=0x3FFF => CTX.regs.bank1 = (value & 0x1F).max(1),
+        0x4000..=0x5FFF => CTX.regs.bank2 = value & 0x03,
+        0x6000..=0x7FFF => CTX.regs.mode = value & 0x01,
+        _ => {
+            if !CTX.regs.ram_enabled {
+                return;
+            }
+
+            let bank = if CTX.regs.mode == 1 {
+                CTX.regs.bank2 as usize
+            } else {
+                0
+            };
+
+            if let Some(offset) = ram_offset(bank, address) {
+                ram_write(offset, value);
+            }
+        }
+    }
+}
+
+unsafe fn mbc2_read(address: u16) -> u8 {
+    match address {
+        0x0000..=0x3FFF => rom_bank_read(0, address),
+        0x4000..=0x7FFF => rom_bank_read(CTX.regs.bank1 as usize, address),
+        _ => {
+            if !CTX.regs.ram_enabled {
+                return 0xFF;
+            }
+
+            0xF0 | CTX.ram_data[(address & 0x1FF) as usize]
+        }
+    }
+}
+
+unsafe fn mbc2_write(address: u16, value: u8) {
+    match address {
+        0x0000..=0x3FFF => {
+            if address & 0x100 == 0 {
+                CTX.regs.ram_enabled = value & 0x0F == 0x0A;
+            } else {
+                CTX.regs.bank1 = (value & 0x0F).max(1);
+            }
+        }
+        0x4000..=0x7FFF => (),
+        _ => {
+            if CTX.regs.ram_enabled {
+                ram_write((address & 0x1FF) as usize, value & 0x0F);
+            }
+        }
+    }
+}
+
+fn host_time_ms() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_millis() as u64,
+        Err(_) => 0,
+    }
+}
+
+fn rtc_valid(regs: &[u8; 5]) -> bool {
+    regs[0] < 60 && regs[1] < 60 && regs[2] < 24
+}
+
+fn rtc_add_days(regs: &mut [u8; 5], days: u64) {
+    let total = (((regs[4] & 1) as u64) << 8 | regs[3] as u64) + days;
+
+    if total > 0x1FF {
+        regs[4] |= 0x80;
+    }
+
+    let total = total & 0x1FF;
+    regs[3] = total as u8;
+    regs[4] = (regs[4] & 0xFE) | (total >> 8) as u8;
+}
+
+// Out of range values keep counting up to their register width before wrapping without carry
+fn rtc_tick_second(regs: &mut [u8; 5]) {
+    let limits = [60, 60, 24];
+
+    for i in 0..3 {
+        let (next, carry) = if regs[i] == limits[i] - 1 {
+            (0, true)
+        } else {
+            ((regs[i] + 1) & RTC_MASKS[i], false)
+        };
+
+        regs[i] = next;
+        if !carry {
+            return;
+        }
+    }
+
+    rtc_add_days(regs, 1);
+}
+
+fn rtc_advance(regs: &mut [u8; 5], mut seconds: u64) {
+    while seconds > 0 && !rtc_valid(regs) {
+        rtc_tick_second(regs);
+        seconds -= 1;
+    }
+
+    let total = regs[2] as u64 * 3600 + regs[1] as u64 * 60 + regs[0] as u64 + seconds;
+    regs[0] = (total % 60) as u8;
+    regs[1] = (total / 60 % 60) as u8;
+    regs[2] = (total / 3600 % 24) as u8;
+
+    rtc_add_days(regs, total / 86400);
+}
+
+// Catches the live registers up with the host clock unless the halt bit is set
+unsafe fn rtc_update() {
+    let now = host_time_ms();
+
+    if CTX.rtc.regs[4] & 0x40 != 0 || now < CTX.rtc.last_time {
+        CTX.rtc.last_time = now;
+        return;
+    }
+
+    let seconds = (now - CTX.rtc.last_time) / 1000;
+    rtc_advance(&mut CTX.rtc.regs, seconds);
+    CTX.rtc.last_time += seconds * 1000;
+}
+
+pub unsafe fn cart_rtc_state() -> RtcContext {
+    rtc_update();
+    CTX.rtc
+}
+
+// Restores a saved clock, advancing it by the host time elapsed since it was saved
+pub unsafe fn cart_rtc_restore(rtc: RtcContext) {
+    CTX.rtc = rtc;
+    rtc_update();
+}
+
+unsafe fn mbc3_read(address: u16) -> u8 {
+    match address {
+        0x0000..=0x3FFF => rom_bank_read(0, address),
+        0x4000..=0x7FFF => rom_bank_read(CTX.regs.bank1 as usize, address),
+        _ => {
+            if !CTX.regs.ram_enabled {
+                return 0xFF;
+            }
+
+            match CTX.regs.bank2 {
+                0x08..=0x0C if CTX.has_rtc => CTX.rtc.latched[(CTX.regs.bank2 - 0x08) as usize],
+                0x00..=0x07 => match ram_offset(CTX.regs.bank2 as usize, address) {
+                    Some(offset) => CTX.ram_data[offset],
+                    None => 0xFF,
+                },
+                _ => 0xFF,
+            }
+        }
+    }
+}
+
+unsafe fn mbc3_write(address: u16, value: u8) {
+    match address {
+        0x0000..=0x1FFF => CTX.regs.ram_enabled = value & 0x0F == 0x0A,
+        0x2000..=0x3FFF => CTX.regs.bank1 = (value & 0x7F).max(1),
+        0x4000..=0x5FFF => CTX.regs.bank2 = value & 0x0F,
+        0x6000..=0x7FFF => {
+            if CTX.has_rtc && CTX.regs.mode == 0x00 && value == 0x01 {
+                rtc_update();
+                CTX.rtc.latched = CTX.rtc.regs;
+            }
+
+            CTX.regs.mode = value;
+        }
+        _ => {
+            if !CTX.regs.ram_enabled {
+                return;
+            }
+
+            match CTX.regs.bank2 {
+                0x08..=0x0C if CTX.has_rtc => {
+                    let index = (CTX.regs.bank2 - 0x08) as usize;
+
+                    rtc_update();
+                    if CTX.rtc.regs[index] != value & RTC_MASKS[index] {
+                        CTX.rtc.regs[index] = value & RTC_MASKS[index];
+                        mark_ram_dirty();
+                    }
+                    CTX.rtc.latched[index] = CTX.rtc.regs[index];
+                }
+                0x00..=0x07 => {
+                    if let Some(offset) = ram_offset(CTX.regs.bank2 as usize, address) {
+                        ram_write(offset, value);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+// Frontend hook for the motor, none is registered by default. Games pulse it many times per frame
+#[allow(dead_code)]
+pub unsafe fn cart_set_rumble_callback(callback: Option<RumbleCallback>) {
+    CTX.rumble_callback = callback;
+}
+
+unsafe fn set_rumble(on: bool) {
+    if CTX.rumble == on {
+        return;
+    }
+
+    CTX.rumble = on;
+
+    if let Some(callback) = CTX.rumble_callback {
+        callback(on);
+    }
+}
+
+unsafe fn mbc5_ram_bank() -> usize {
+    if CTX.has_rumble {
+        (CTX.regs.bank2 & 0x07) as usize
+    } else {
+        CTX.regs.bank2 as usize
+    }
+}
+
+unsafe fn mbc5_read(address: u16) -> u8 {
+    match address {
+        0x0000..=0x3FFF => rom_bank_read(0, address),
+        0x4000..=0x7FFF => {
+            let bank = ((CTX.regs.rom_bank_high as usize) << 8) | CTX.regs.bank1 as usize;
+            rom_bank_read(bank, address)
+        }
+        _ => {
+            if !CTX.regs.ram_enabled {
+                return 0xFF;
+            }
+
+            match ram_offset(mbc5_ram_bank(), address) {
+                Some(offset) => CTX.ram_data[offset],
+                None => 0xFF,
+            }
+        }
+    }
+}
+
+unsafe fn mbc5_write(address: u16, value: u8) {
+    match address {
+        0x0000..=0x1FFF => CTX.regs.ram_enabled = value == 0x0A,
+        0x2000..=0x2FFF => CTX.regs.bank1 = value,
+        0x3000..=0x3FFF => CTX.regs.rom_bank_high = value & 0x01,
+        0x4000..=0x5FFF => {
+            CTX.regs.bank2 = value & 0x0F;
+
+            if CTX.has_rumble {
+                set_rumble(value & 0x08 != 0);
+            }
+        }
+        0x6000..=0x7FFF => (),
+        _ => {
+            if !CTX.regs.ram_enabled {
+                return;
+            }
+
+            if let Some(offset) = ram_offset(mbc5_ram_bank(), address) {
+                ram_write(offset, value);
+            }
+        }
+    }
+}
+
+unsafe fn rom_only_read(address: u16) -> u8 {
+    if (0xA000..0xC000).contains(&address) {
+        //External RAM, open bus when the cart has none
+        return match ram_offset(0, address) {
+            Some(offset) => CTX.ram_data[offset],
+            None => 0xFF,
+        };
+    }
+
+    rom_bank_read((address >> 14) as usize, address)
+}
+
+unsafe fn rom_only_write(address: u16, value: u8) {
+    if (0xA000..0xC000).contains(&address) {
+        if let Some(offset) = ram_offset(0, address) {
+            ram_write(offset, value);
+        }
+    }
+}
+
 pub unsafe fn cart_read(address: u16) -> u8 {
-    let address = address as usize;
-    CTX.rom_data[address]
+    match CTX.mbc {
+        MbcType::MBC_NONE => rom_only_read(address),
+        MbcType::MBC1 | MbcType::MBC1M => mbc1_read(address),
+        MbcType::MBC2 => mbc2_read(address),
+        MbcType::MBC3 => mbc3_read(address),
+        MbcType::MBC5 => mbc5_read(address),
+    }
 }
 
-pub fn cart_write(address: u16, value: u8) {
-    panic!("Cart Write ({:04X})", address);
+pub unsafe fn cart_write(address: u16, value: u8) {
+    match CTX.mbc {
+        MbcType::MBC_NONE => rom_only_write(address, value),
+        MbcType::MBC1 | MbcType::MBC1M => mbc1_write(address, value),
+        MbcType::MBC2 => mbc2_write(address, value),
+        MbcType::MBC3 => mbc3_write(address, value),
+        MbcType::MBC5 => mbc5_write(address, value),
+    }
 }
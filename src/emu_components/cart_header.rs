@@ -0,0 +1,807 @@
+use super::common::convert_to_u16;
+use std::error::Error;
+use std::fmt;
+
+/*
+  Cartridge header, 0x0100 - 0x014F:
+
+  0x0100 - 0x0103 : entry point
+  0x0104 - 0x0133 : Nintendo logo
+  0x0134 - 0x0143 : title
+  0x013F - 0x0142 : manufacturer code
+  0x0143          : CGB flag
+  0x0144 - 0x0145 : new licensee code
+  0x0146          : SGB flag
+  0x0147          : cartridge type
+  0x0148          : ROM size, 32 KiB << n
+  0x0149          : RAM size
+  0x014A          : destination code
+  0x014B          : old licensee code
+  0x014C          : mask ROM version
+  0x014D          : header checksum over 0x0134 - 0x014C
+  0x014E - 0x014F : global checksum, big endian sum of every other byte
+
+  The boot ROM locks up on a bad logo or header checksum while the global
+  checksum is never verified by hardware, so it is only ever reported as a
+  warning. Lenient parsing, for homebrew, only rejects truncated files.
+*/
+
+#[derive(Debug)]
+pub struct RomHeader {
+    pub entry: [u8; 4],
+    pub logo: [u8; 48],
+    pub title: [u8; 16],
+    pub manufacturer_code: [u8; 4],
+    pub cgb_flag: u8,
+    pub new_lic_code: u16,
+    pub sgb_flag: u8,
+    pub c_type: u8,
+    pub rom_size: u8,
+    pub ram_size: u8,
+    pub dest_code: u8,
+    pub lic_code: u8,
+    pub version: u8,
+    pub checksum: u8,
+    pub global_checksum: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderError {
+    Truncated {
+        size: usize,
+    },
+    UnknownMapper(u8),
+    RomSizeMismatch {
+        code: u8,
+        expected: Option<usize>,
+        actual: usize,
+    },
+    BadLogo,
+    BadHeaderChecksum {
+        expected: u8,
+        actual: u8,
+    },
+    BadGlobalChecksum {
+        expected: u16,
+        actual: u16,
+    },
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeaderError::Truncated { size } => {
+                write!(f, "file is {} bytes, too short to hold a header", size)
+            }
+            HeaderError::UnknownMapper(c_type) => {
+                write!(f, "unknown cartridge type {:02X}", c_type)
+            }
+            HeaderError::RomSizeMismatch {
+                code,
+                expected: Some(expected),
+                actual,
+            } => write!(
+                f,
+                "ROM size code {:02X} declares {} bytes but the file is {} bytes",
+                code, expected, actual
+            ),
+            HeaderError::RomSizeMismatch { code, .. } => {
+                write!(f, "unknown ROM size code {:02X}", code)
+            }
+            HeaderError::BadLogo => write!(f, "Nintendo logo does not match"),
+            HeaderError::BadHeaderChecksum { expected, actual } => write!(
+                f,
+                "header checksum is {:02X}, computed {:02X}",
+                expected, actual
+            ),
+            HeaderError::BadGlobalChecksum { expected, actual } => write!(
+                f,
+                "global checksum is {:04X}, computed {:04X}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl Error for HeaderError {}
+
+// 0x0143, 0x80 runs on both models and 0xC0 only on CGB
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CgbSupport {
+    CGB_NONE,
+    CGB_COMPATIBLE,
+    CGB_ONLY,
+}
+
+// 0x014A, 0x00 Japan and 0x01 everywhere else
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Destination {
+    DEST_JAPAN,
+    DEST_OVERSEAS,
+    DEST_UNKNOWN,
+}
+
+pub static NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+impl RomHeader {
+    // Parses the header out of a whole ROM image, see check() for what strict mode rejects
+    pub fn parse(rom: &[u8], lenient: bool) -> Result<RomHeader, HeaderError> {
+        if rom.len() < 0x150 {
+            return Err(HeaderError::Truncated { size: rom.len() });
+        }
+
+        let mut header = RomHeader {
+            entry: [0; 4],
+            logo: [0; 48],
+            title: [0; 16],
+            manufacturer_code: [0; 4],
+            cgb_flag: rom[0x143],
+            new_lic_code: convert_to_u16(rom[0x144], rom[0x145]),
+            sgb_flag: rom[0x146],
+            c_type: rom[0x147],
+            rom_size: rom[0x148],
+            ram_size: rom[0x149],
+            dest_code: rom[0x14A],
+            lic_code: rom[0x14B],
+            version: rom[0x14C],
+            checksum: rom[0x14D],
+            global_checksum: convert_to_u16(rom[0x14E], rom[0x14F]),
+        };
+
+        header.entry.copy_from_slice(&rom[0x100..=0x103]);
+        header.logo.copy_from_slice(&rom[0x104..=0x133]);
+        header.title.copy_from_slice(&rom[0x134..=0x143]);
+        header
+            .manufacturer_code
+            .copy_from_slice(&rom[0x13F..=0x142]);
+
+        if !lenient {
+            let fatal = header
+                .check(rom)
+                .into_iter()
+                .find(|e| !matches!(e, HeaderError::BadGlobalChecksum { .. }));
+
+            if let Some(e) = fatal {
+                return Err(e);
+            }
+        }
+
+        Ok(header)
+    }
+
+    // Every problem found with the header, in header order
+    pub fn check(&self, rom: &[u8]) -> Vec<HeaderError> {
+        let mut errors = Vec::new();
+
+        if self.logo != NINTENDO_LOGO {
+            errors.push(HeaderError::BadLogo);
+        }
+
+        if rom_type_name(self.c_type).is_none() {
+            errors.push(HeaderError::UnknownMapper(self.c_type));
+        }
+
+        let expected = rom_size_bytes(self.rom_size);
+        if expected != Some(rom.len()) {
+            errors.push(HeaderError::RomSizeMismatch {
+                code: self.rom_size,
+                expected,
+                actual: rom.len(),
+            });
+        }
+
+        let actual = header_checksum(rom);
+        if actual != self.checksum {
+            errors.push(HeaderError::BadHeaderChecksum {
+                expected: self.checksum,
+                actual,
+            });
+        }
+
+        let actual = global_checksum(rom);
+        if actual != self.global_checksum {
+            errors.push(HeaderError::BadGlobalChecksum {
+                expected: self.global_checksum,
+                actual,
+            });
+        }
+
+        errors
+    }
+
+    /*
+      Older carts use all 16 bytes for the title. Newer ones shrink it to
+      15 to make room for the CGB flag, and to 11 when a manufacturer code
+      follows it. Padding is either 0x00 or spaces.
+    */
+    pub fn title(&self) -> String {
+        let len = if self.manufacturer_code().is_some() {
+            11
+        } else if self.cgb_flag & 0x80 != 0 {
+            15
+        } else {
+            16
+        };
+
+        let title = &self.title[..len];
+        let end = title.iter().position(|c| *c == 0).unwrap_or(len);
+
+        String::from_utf8_lossy(&title[..end])
+            .trim_end()
+            .to_string()
+    }
+
+    // Only newer carts carry a 4-character manufacturer code between the title and the CGB flag
+    pub fn manufacturer_code(&self) -> Option<String> {
+        let code = &self.manufacturer_code;
+
+        if self.cgb_flag & 0x80 == 0
+            || !code
+                .iter()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        {
+            return None;
+        }
+
+        Some(String::from_utf8_lossy(code).to_string())
+    }
+
+    pub fn cgb_support(&self) -> CgbSupport {
+        match self.cgb_flag {
+            0xC0 => CgbSupport::CGB_ONLY,
+            flag if flag & 0x80 != 0 => CgbSupport::CGB_COMPATIBLE,
+            _ => CgbSupport::CGB_NONE,
+        }
+    }
+
+    // SGB functions also need the old licensee code to be 0x33
+    pub fn sgb_support(&self) -> bool {
+        self.sgb_flag == 0x03 && self.lic_code == 0x33
+    }
+
+    pub fn destination(&self) -> Destination {
+        match self.dest_code {
+            0x00 => Destination::DEST_JAPAN,
+            0x01 => Destination::DEST_OVERSEAS,
+            _ => Destination::DEST_UNKNOWN,
+        }
+    }
+
+    // The two new licensee characters, only meaningful when the old code is 0x33
+    pub fn new_licensee_code(&self) -> Option<String> {
+        if self.lic_code != 0x33 {
+            return None;
+        }
+
+        Some(String::from_utf8_lossy(&self.new_lic_code.to_be_bytes()).to_string())
+    }
+
+    pub fn licensee(&self) -> String {
+        match self.new_licensee_code() {
+            Some(code) => new_lic_code(&code),
+            None => lic_code(self.lic_code),
+        }
+    }
+
+    pub fn rom_type(&self) -> Option<&'static str> {
+        rom_type_name(self.c_type)
+    }
+
+    pub fn rom_size_bytes(&self) -> Option<usize> {
+        rom_size_bytes(self.rom_size)
+    }
+
+    // External RAM only, MBC2's built-in RAM is not declared in the header
+    pub fn ram_size_kib(&self) -> usize {
+        ram_size_bytes(self.ram_size) / 1024
+    }
+
+    // One "\t Name     : value" line per field, sizes and checksums checked against the ROM image
+    pub fn write_summary(&self, f: &mut dyn fmt::Write, rom: &[u8]) -> fmt::Result {
+        writeln!(f, "\t Title    : {}", self.title())?;
+        if let Some(code) = self.manufacturer_code() {
+            writeln!(f, "\t Mfr Code : {}", code)?;
+        }
+        writeln!(
+            f,
+            "\t Type     : {:02X} ({})",
+            self.c_type,
+            self.rom_type().unwrap_or("UNKNOWN")
+        )?;
+        match self.rom_size_bytes() {
+            Some(size) => writeln!(
+                f,
+                "\t ROM Size : {} KB (file {} KB)",
+                size / 1024,
+                rom.len() / 1024
+            )?,
+            None => writeln!(
+                f,
+                "\t ROM Size : unknown {:02X} (file {} KB)",
+                self.rom_size,
+                rom.len() / 1024
+            )?,
+        }
+        writeln!(f, "\t RAM Size : {} KB", self.ram_size_kib())?;
+        match self.new_licensee_code() {
+            Some(code) => writeln!(f, "\t LIC Code : {} {}", code, self.licensee())?,
+            None => writeln!(f, "\t LIC Code : {:02X} {}", self.lic_code, self.licensee())?,
+        }
+        writeln!(f, "\t CGB      : {:?}", self.cgb_support())?;
+        writeln!(f, "\t SGB      : {}", self.sgb_support())?;
+        writeln!(f, "\t Dest     : {:?}", self.destination())?;
+        writeln!(f, "\t ROM Vers : {}", self.version)?;
+        writeln!(
+            f,
+            "\t Checksum : {:02X} ({})",
+            self.checksum,
+            pass(header_checksum(rom) == self.checksum)
+        )?;
+        writeln!(
+            f,
+            "\t Global   : {:04X} ({})",
+            self.global_checksum,
+            pass(global_checksum(rom) == self.global_checksum)
+        )
+    }
+}
+
+fn pass(ok: bool) -> &'static str {
+    if ok {
+        "PASSED"
+    } else {
+        "FAILED"
+    }
+}
+
+pub fn header_checksum(rom: &[u8]) -> u8 {
+    let mut x: u8 = 0;
+    for value in &rom[0x134..=0x14C] {
+        x = x.wrapping_sub(*value).wrapping_sub(1);
+    }
+
+    x
+}
+
+pub fn global_checksum(rom: &[u8]) -> u16 {
+    let mut x: u16 = 0;
+    for (i, value) in rom.iter().enumerate() {
+        if i != 0x14E && i != 0x14F {
+            x = x.wrapping_add(*value as u16);
+        }
+    }
+
+    x
+}
+
+static ROM_TYPES: [&str; 35] = [
+    "ROM ONLY",
+    "MBC1",
+    "MBC1+RAM",
+    "MBC1+RAM+BATTERY",
+    "0x04 ???",
+    "MBC2",
+    "MBC2+BATTERY",
+    "0x07 ???",
+    "ROM+RAM 1",
+    "ROM+RAM+BATTERY 1",
+    "0x0A ???",
+    "MMM01",
+    "MMM01+RAM",
+    "MMM01+RAM+BATTERY",
+    "0x0E ???",
+    "MBC3+TIMER+BATTERY",
+    "MBC3+TIMER+RAM+BATTERY 2",
+    "MBC3",
+    "MBC3+RAM 2",
+    "MBC3+RAM+BATTERY 2",
+    "0x14 ???",
+    "0x15 ???",
+    "0x16 ???",
+    "0x17 ???",
+    "0x18 ???",
+    "MBC5",
+    "MBC5+RAM",
+    "MBC5+RAM+BATTERY",
+    "MBC5+RUMBLE",
+    "MBC5+RUMBLE+RAM",
+    "MBC5+RUMBLE+RAM+BATTERY",
+    "0x1F ???",
+    "MBC6",
+    "0x21 ???",
+    "MBC7+SENSOR+RUMBLE+RAM+BATTERY",
+];
+
+pub fn rom_type_name(c_type: u8) -> Option<&'static str> {
+    match c_type {
+        0xFC => Some("POCKET CAMERA"),
+        0xFD => Some("BANDAI TAMA5"),
+        0xFE => Some("HuC3"),
+        0xFF => Some("HuC1+RAM+BATTERY"),
+        _ => ROM_TYPES
+            .get(c_type as usize)
+            .copied()
+            .filter(|name| !name.ends_with("???")),
+    }
+}
+
+// 0x52 - 0x54 only show up in a few unofficial headers
+pub fn rom_size_bytes(code: u8) -> Option<usize> {
+    match code {
+        0x00..=0x08 => Some(0x8000 << code),
+        0x52 => Some(72 * 0x4000),
+        0x53 => Some(80 * 0x4000),
+        0x54 => Some(96 * 0x4000),
+        _ => None,
+    }
+}
+
+pub fn ram_size_bytes(code: u8) -> usize {
+    match code {
+        0x01 => 0x800,
+        0x02 => 0x2000,
+        0x03 => 0x8000,
+        0x04 => 0x20000,
+        0x05 => 0x10000,
+        _ => 0,
+    }
+}
+
+// Old licensee code at 0x014B
+pub fn lic_code(code: u8) -> String {
+    let lic_code = vec![
+        (0x00, "None"),
+        (0x01, "Nintendo"),
+        (0x08, "Capcom"),
+        (0x09, "HOT-B"),
+        (0x0A, "Jaleco"),
+        (0x0B, "Coconuts Japan"),
+        (0x0C, "Elite Systems"),
+        (0x13, "Electronic Arts"),
+        (0x18, "Hudson Soft"),
+        (0x19, "ITC Entertainment"),
+        (0x1A, "Yanoman"),
+        (0x1D, "Japan Clary"),
+        (0x1F, "Virgin Games"),
+        (0x24, "PCM Complete"),
+        (0x25, "San-X"),
+        (0x28, "Kemco"),
+        (0x29, "SETA"),
+        (0x30, "Infogrames"),
+        (0x31, "Nintendo"),
+        (0x32, "Bandai"),
+        (0x34, "Konami"),
+        (0x35, "HectorSoft"),
+        (0x38, "Capcom"),
+        (0x39, "Banpresto"),
+        (0x3C, "Entertainment Interactive"),
+        (0x3E, "Gremlin"),
+        (0x41, "Ubi Soft"),
+        (0x42, "Atlus"),
+        (0x44, "Malibu"),
+        (0x46, "Angel"),
+        (0x47, "Spectrum HoloByte"),
+        (0x49, "Irem"),
+        (0x4A, "Virgin Games"),
+        (0x4D, "Malibu"),
+        (0x4F, "U.S. Gold"),
+        (0x50, "Absolute"),
+        (0x51, "Acclaim"),
+        (0x52, "Activision"),
+        (0x53, "Sammy USA"),
+        (0x54, "GameTek"),
+        (0x55, "Park Place"),
+        (0x56, "LJN"),
+        (0x57, "Matchbox"),
+        (0x59, "Milton Bradley"),
+        (0x5A, "Mindscape"),
+        (0x5B, "Romstar"),
+        (0x5C, "Naxat Soft"),
+        (0x5D, "Tradewest"),
+        (0x60, "Titus"),
+        (0x61, "Virgin Games"),
+        (0x67, "Ocean"),
+        (0x69, "Electronic Arts"),
+        (0x6E, "Elite Systems"),
+        (0x6F, "Electro Brain"),
+        (0x70, "Infogrames"),
+        (0x71, "Interplay"),
+        (0x72, "Broderbund"),
+        (0x73, "Sculptured Software"),
+        (0x75, "The Sales Curve"),
+        (0x78, "THQ"),
+        (0x79, "Accolade"),
+        (0x7A, "Triffix Entertainment"),
+        (0x7C, "MicroProse"),
+        (0x7F, "Kemco"),
+        (0x80, "Misawa Entertainment"),
+        (0x83, "LOZC"),
+        (0x86, "Tokuma Shoten"),
+        (0x8B, "Bullet-Proof Software"),
+        (0x8C, "Vic Tokai"),
+        (0x8E, "Ape"),
+        (0x8F, "I'Max"),
+        (0x91, "Chunsoft"),
+        (0x92, "Video System"),
+        (0x93, "Tsubaraya Productions"),
+        (0x95, "Varie"),
+        (0x96, "Yonezawa/S'Pal"),
+        (0x97, "Kemco"),
+        (0x99, "Arc"),
+        (0x9A, "Nihon Bussan"),
+        (0x9B, "Tecmo"),
+        (0x9C, "Imagineer"),
+        (0x9D, "Banpresto"),
+        (0x9F, "Nova"),
+        (0xA1, "Hori Electric"),
+        (0xA2, "Bandai"),
+        (0xA4, "Konami"),
+        (0xA6, "Kawada"),
+        (0xA7, "Takara"),
+        (0xA9, "Technos Japan"),
+        (0xAA, "Broderbund"),
+        (0xAC, "Toei Animation"),
+        (0xAD, "Toho"),
+        (0xAF, "Namco"),
+        (0xB0, "Acclaim"),
+        (0xB1, "ASCII or Nexsoft"),
+        (0xB2, "Bandai"),
+        (0xB4, "Square Enix"),
+        (0xB6, "HAL Laboratory"),
+        (0xB7, "SNK"),
+        (0xB9, "Pony Canyon"),
+        (0xBA, "Culture Brain"),
+        (0xBB, "Sunsoft"),
+        (0xBD, "Sony Imagesoft"),
+        (0xBF, "Sammy"),
+        (0xC0, "Taito"),
+        (0xC2, "Kemco"),
+        (0xC3, "Square"),
+        (0xC4, "Tokuma Shoten"),
+        (0xC5, "Data East"),
+        (0xC6, "Tonkin House"),
+        (0xC8, "Koei"),
+        (0xC9, "UFL"),
+        (0xCA, "Ultra Games"),
+        (0xCB, "VAP"),
+        (0xCC, "Use Corporation"),
+        (0xCD, "Meldac"),
+        (0xCE, "Pony Canyon"),
+        (0xCF, "Angel"),
+        (0xD0, "Taito"),
+        (0xD1, "SOFEL"),
+        (0xD2, "Quest"),
+        (0xD3, "Sigma Enterprises"),
+        (0xD4, "ASK Kodansha"),
+        (0xD6, "Naxat Soft"),
+        (0xD7, "Copya System"),
+        (0xD9, "Banpresto"),
+        (0xDA, "Tomy"),
+        (0xDB, "LJN"),
+        (0xDD, "Nippon Computer Systems"),
+        (0xDE, "Human Entertainment"),
+        (0xDF, "Altron"),
+        (0xE0, "Jaleco"),
+        (0xE1, "Towa Chiki"),
+        (0xE2, "Yutaka"),
+        (0xE3, "Varie"),
+        (0xE5, "Epoch"),
+        (0xE7, "Athena"),
+        (0xE8, "Asmik Ace Entertainment"),
+        (0xE9, "Natsume"),
+        (0xEA, "King Records"),
+        (0xEB, "Atlus"),
+        (0xEC, "Epic/Sony Records"),
+        (0xEE, "IGS"),
+        (0xF0, "A Wave"),
+        (0xF3, "Extreme Entertainment"),
+        (0xFF, "LJN"),
+    ];
+
+    match lic_code.iter().find(|(lic_code, _)| lic_code == &code) {
+        Some((_, string)) => string.to_string(),
+        None => "UNKNOWN".to_string(),
+    }
+}
+
+// Two ASCII characters at 0x0144 - 0x0145, used when the old licensee code is 0x33
+pub fn new_lic_code(code: &str) -> String {
+    let lic_code = vec![
+        ("00", "None"),
+        ("01", "Nintendo R&D1"),
+        ("08", "Capcom"),
+        ("13", "Electronic Arts"),
+        ("18", "Hudson Soft"),
+        ("19", "b-ai"),
+        ("20", "kss"),
+        ("22", "pow"),
+        ("24", "PCM Complete"),
+        ("25", "san-x"),
+        ("28", "Kemco Japan"),
+        ("29", "seta"),
+        ("30", "Viacom"),
+        ("31", "Nintendo"),
+        ("32", "Bandai"),
+        ("33", "Ocean/Acclaim"),
+        ("34", "Konami"),
+        ("35", "Hector"),
+        ("37", "Taito"),
+        ("38", "Hudson"),
+        ("39", "Banpresto"),
+        ("41", "Ubi Soft"),
+        ("42", "Atlus"),
+        ("44", "Malibu"),
+        ("46", "angel"),
+        ("47", "Bullet-Proof"),
+        ("49", "irem"),
+        ("50", "Absolute"),
+        ("51", "Acclaim"),
+        ("52", "Activision"),
+        ("53", "American sammy"),
+        ("54", "Konami"),
+        ("55", "Hi tech entertainment"),
+        ("56", "LJN"),
+        ("57", "Matchbox"),
+        ("58", "Mattel"),
+        ("59", "Milton Bradley"),
+        ("60", "Titus"),
+        ("61", "Virgin"),
+        ("64", "LucasArts"),
+        ("67", "Ocean"),
+        ("69", "Electronic Arts"),
+        ("70", "Infogrames"),
+        ("71", "Interplay"),
+        ("72", "Broderbund"),
+        ("73", "sculptured"),
+        ("75", "sci"),
+        ("78", "THQ"),
+        ("79", "Accolade"),
+        ("80", "misawa"),
+        ("83", "lozc"),
+        ("86", "Tokuma Shoten Intermedia"),
+        ("87", "Tsukuda Original"),
+        ("91", "Chunsoft"),
+        ("92", "Video system"),
+        ("93", "Ocean/Acclaim"),
+        ("95", "Varie"),
+        ("96", "Yonezawa/s’pal"),
+        ("97", "Kaneko"),
+        ("99", "Pack in soft"),
+        ("9H", "Bottom Up"),
+        ("A4", "Konami (Yu-Gi-Oh!)"),
+        ("BL", "MTO"),
+        ("DK", "Kodansha"),
+    ];
+
+    match lic_code.iter().find(|(lic_code, _)| *lic_code == code) {
+        Some((_, string)) => string.to_string(),
+        None => "UNKNOWN".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 32 KiB ROM-only image with a valid logo and checksums
+    fn test_rom() -> Vec<u8> {
+        let mut rom = vec![0; 0x8000];
+        rom[0x104..=0x133].copy_from_slice(&NINTENDO_LOGO);
+        rom[0x134..0x138].copy_from_slice(b"TEST");
+        fix_checksums(&mut rom);
+        rom
+    }
+
+    fn fix_checksums(rom: &mut [u8]) {
+        rom[0x14D] = header_checksum(rom);
+        let global = global_checksum(rom);
+        rom[0x14E..=0x14F].copy_from_slice(&global.to_be_bytes());
+    }
+
+    #[test]
+    fn parses_valid_header() {
+        let rom = test_rom();
+        let header = RomHeader::parse(&rom, false).unwrap();
+
+        assert_eq!(header.title(), "TEST");
+        assert_eq!(header.rom_type(), Some("ROM ONLY"));
+        assert!(header.check(&rom).is_empty());
+    }
+
+    #[test]
+    fn rejects_truncated_rom() {
+        let rom = test_rom();
+
+        for lenient in [false, true] {
+            assert_eq!(
+                RomHeader::parse(&rom[..0x14F], lenient).unwrap_err(),
+                HeaderError::Truncated { size: 0x14F }
+            );
+        }
+    }
+
+    #[test]
+    fn bad_header_checksum() {
+        let mut rom = test_rom();
+        rom[0x14D] ^= 0xFF;
+        let expected = rom[0x14D];
+        let actual = header_checksum(&rom);
+
+        assert_eq!(
+            RomHeader::parse(&rom, false).unwrap_err(),
+            HeaderError::BadHeaderChecksum { expected, actual }
+        );
+
+        let header = RomHeader::parse(&rom, true).unwrap();
+        assert!(header
+            .check(&rom)
+            .contains(&HeaderError::BadHeaderChecksum { expected, actual }));
+    }
+
+    #[test]
+    fn bad_global_checksum_is_not_fatal() {
+        let mut rom = test_rom();
+        rom[0x14E] ^= 0xFF;
+
+        let header = RomHeader::parse(&rom, false).unwrap();
+        assert!(matches!(
+            header.check(&rom)[..],
+            [HeaderError::BadGlobalChecksum { .. }]
+        ));
+    }
+
+    #[test]
+    fn unknown_mapper_strict_and_lenient() {
+        let mut rom = test_rom();
+        rom[0x147] = 0xEE;
+        fix_checksums(&mut rom);
+
+        assert_eq!(
+            RomHeader::parse(&rom, false).unwrap_err(),
+            HeaderError::UnknownMapper(0xEE)
+        );
+
+        let header = RomHeader::parse(&rom, true).unwrap();
+        assert_eq!(header.rom_type(), None);
+        assert_eq!(header.check(&rom), vec![HeaderError::UnknownMapper(0xEE)]);
+    }
+
+    #[test]
+    fn rom_size_mismatch() {
+        let mut rom = test_rom();
+        rom[0x148] = 0x01;
+        fix_checksums(&mut rom);
+
+        assert_eq!(
+            RomHeader::parse(&rom, false).unwrap_err(),
+            HeaderError::RomSizeMismatch {
+                code: 0x01,
+                expected: Some(0x10000),
+                actual: 0x8000
+            }
+        );
+    }
+
+    #[test]
+    fn summary_checks_sizes_and_checksums() {
+        let mut rom = test_rom();
+        rom[0x148] = 0x09;
+        fix_checksums(&mut rom);
+        rom[0x14E] ^= 0xFF;
+
+        let header = RomHeader::parse(&rom, true).unwrap();
+        let mut summary = String::new();
+        header.write_summary(&mut summary, &rom).unwrap();
+
+        assert!(summary.contains("\t Title    : TEST\n"));
+        assert!(summary.contains("\t ROM Size : unknown 09 (file 32 KB)\n"));
+        assert!(summary.contains("(PASSED)\n\t Global"));
+        assert!(summary.ends_with("(FAILED)\n"));
+    }
+}
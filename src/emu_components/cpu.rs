@@ -1,8 +1,13 @@
+use super::boot::boot_rom_mapped;
 use super::bus::bus_read;
+use super::cart::cart_header;
 use super::cpu_fetch::fetch_data;
 use super::cpu_proc::inst_get_processor;
+use super::emu::{emu_cgb_mode, emu_cycles, emu_model};
 use super::instructions::{instruction_by_opcode, set_instructions};
 use super::instructions::{AddrMode, CondType, InType, Instruction, RegType};
+use super::interrupts::{cpu_handle_interrupts, interrupt_pending};
+use super::model::HardwareModel;
 
 #[derive(Debug)]
 pub struct CpuRegisters {
@@ -30,11 +35,14 @@ pub struct CpuContext {
     pub cur_inst: Instruction,
 
     pub halted: bool,
+    pub halt_bug: bool,
     pub stepping: bool,
 
     pub int_master_enabled: bool,
+    pub enabling_ime: bool,
 
     pub ie_register: u8,
+    pub int_flags: u8,
 }
 
 static mut CTX: CpuContext = CpuContext {
@@ -55,9 +63,12 @@ static mut CTX: CpuContext = CpuContext {
     dest_is_mem: false,
     cur_opcode: 0,
     halted: false,
+    halt_bug: false,
     stepping: false,
     int_master_enabled: true,
+    enabling_ime: false,
     ie_register: 0,
+    int_flags: 0,
     cur_inst: Instruction {
         i_type: InType::IN_NONE,
         mode: AddrMode::AM_IMP,
@@ -74,12 +85,109 @@ pub unsafe fn cpu_get_context() -> &'static mut CpuContext {
 
 pub unsafe fn cpu_init() {
     set_instructions();
-    CTX.regs.pc = 0x100;
+
+    if boot_rom_mapped() {
+        // Power-on state, the boot ROM sets up everything else itself
+        CTX.regs = CpuRegisters {
+            a: 0,
+            f: 0,
+            b: 0,
+            c: 0,
+            d: 0,
+            e: 0,
+            h: 0,
+            l: 0,
+            pc: 0,
+            sp: 0,
+        };
+        CTX.int_master_enabled = false;
+    } else {
+        CTX.regs = cpu_post_boot_registers(emu_model());
+        CTX.int_master_enabled = false;
+        // The boot ROM finishes during VBlank with the interrupt still flagged
+        CTX.int_flags = 0x01;
+    }
+}
+
+/*
+  Registers at 0x0100 when the boot ROM hands over:
+
+  Model         A   F   B   C   D   E   H   L
+  DMG0          01  00  FF  13  00  C1  84  03
+  DMG           01  B0  00  13  00  D8  01  4D
+  MGB           FF  B0  00  13  00  D8  01  4D
+  SGB           01  00  00  14  00  00  C0  60
+  SGB2          FF  00  00  14  00  00  C0  60
+  CGB           11  80  00  00  FF  56  00  0D
+  CGB, DMG cart 11  80  *   00  00  08  *   *
+
+  DMG and MGB clear H and C when the header checksum is 0x00. For a DMG
+  cartridge the CGB boot ROM leaves the sum of the title in B if the
+  licensee is Nintendo (0 otherwise), and HL = 0x991A if that sum is 0x43
+  or 0x58, 0x007C otherwise. The AGB runs the same boot ROM with an extra
+  INC B at the end, which also sets F.
+*/
+unsafe fn cpu_post_boot_registers(model: HardwareModel) -> CpuRegisters {
+    let header = cart_header();
+
+    let dmg_f = if header.checksum == 0 { 0x80 } else { 0xB0 };
+
+    let (a, mut f, mut b, c, d, e, h, l) = match model {
+        HardwareModel::DMG0 => (0x01, 0x00, 0xFF, 0x13, 0x00, 0xC1, 0x84, 0x03),
+        HardwareModel::DMG => (0x01, dmg_f, 0x00, 0x13, 0x00, 0xD8, 0x01, 0x4D),
+        HardwareModel::MGB => (0xFF, dmg_f, 0x00, 0x13, 0x00, 0xD8, 0x01, 0x4D),
+        HardwareModel::SGB => (0x01, 0x00, 0x00, 0x14, 0x00, 0x00, 0xC0, 0x60),
+        HardwareModel::SGB2 => (0xFF, 0x00, 0x00, 0x14, 0x00, 0x00, 0xC0, 0x60),
+        _ if emu_cgb_mode() => (0x11, 0x80, 0x00, 0x00, 0xFF, 0x56, 0x00, 0x0D),
+        _ => {
+            let nintendo =
+                header.lic_code == 0x01 || header.new_licensee_code().as_deref() == Some("01");
+
+            let b = if nintendo {
+                header.title.iter().fold(0u8, |sum, x| sum.wrapping_add(*x))
+            } else {
+                0
+            };
+
+            let (h, l) = if b == 0x43 || b == 0x58 {
+                (0x99, 0x1A)
+            } else {
+                (0x00, 0x7C)
+            };
+
+            (0x11, 0x80, b, 0x00, 0x00, 0x08, h, l)
+        }
+    };
+
+    if model == HardwareModel::AGB {
+        let result = b.wrapping_add(1);
+        f = if result == 0 { 0x80 } else { 0x00 } | if b & 0x0F == 0x0F { 0x20 } else { 0x00 };
+        b = result;
+    }
+
+    CpuRegisters {
+        a,
+        f,
+        b,
+        c,
+        d,
+        e,
+        h,
+        l,
+        pc: 0x100,
+        sp: 0xFFFE,
+    }
 }
 
 unsafe fn fetch_instruction() {
     CTX.cur_opcode = bus_read(CTX.regs.pc);
-    CTX.regs.pc += 1;
+
+    // HALT bug: the byte after HALT is read twice because PC fails to increment
+    if CTX.halt_bug {
+        CTX.halt_bug = false;
+    } else {
+        CTX.regs.pc = CTX.regs.pc.wrapping_add(1);
+    }
 
     CTX.cur_inst = match instruction_by_opcode(CTX.cur_opcode) {
         Some(x) => x,
@@ -96,6 +204,7 @@ pub unsafe fn cpu_step() -> bool {
     let pc = CTX.regs.pc;
     if !CTX.halted {
         fetch_instruction();
+        emu_cycles(1);
         fetch_data();
 
         println!(
@@ -115,7 +224,25 @@ pub unsafe fn cpu_step() -> bool {
         );
 
         execute();
+    } else {
+        //is halted...
+        emu_cycles(1);
+
+        if interrupt_pending(&CTX) != 0 {
+            CTX.halted = false;
+        }
     }
+
+    if CTX.int_master_enabled {
+        cpu_handle_interrupts(&mut CTX);
+        CTX.enabling_ime = false;
+    }
+
+    // EI takes effect after the instruction that follows it
+    if CTX.enabling_ime {
+        CTX.int_master_enabled = true;
+    }
+
     true
 }
 
@@ -126,3 +253,11 @@ pub unsafe fn cpu_get_ie_register() -> u8 {
 pub unsafe fn cpu_set_ie_register(n: u8) {
     CTX.ie_register = n;
 }
+
+pub unsafe fn cpu_get_int_flags() -> u8 {
+    CTX.int_flags
+}
+
+pub unsafe fn cpu_set_int_flags(value: u8) {
+    CTX.int_flags = value & 0x1F;
+}
@@ -44,10 +44,10 @@ pub unsafe fn fetch_data() {
             }
         }
         AddrMode::AM_R_MR => {
-            let addr = cpu_read_reg(ctx.cur_inst.reg_2);
+            let mut addr = cpu_read_reg(ctx.cur_inst.reg_2);
 
             if ctx.cur_inst.reg_2 == RegType::RT_C {
-                ctx.mem_dest |= 0xFF00;
+                addr |= 0xFF00;
             }
 
             ctx.fetched_data = bus_read(addr) as u16;
@@ -56,24 +56,24 @@ pub unsafe fn fetch_data() {
         AddrMode::AM_R_HLI => {
             ctx.fetched_data = bus_read(cpu_read_reg(ctx.cur_inst.reg_2)) as u16;
             emu_cycles(1);
-            cpu_set_reg(RegType::RT_HL, cpu_read_reg(RegType::RT_HL) + 1);
+            cpu_set_reg(RegType::RT_HL, cpu_read_reg(RegType::RT_HL).wrapping_add(1));
         }
         AddrMode::AM_R_HLD => {
             ctx.fetched_data = bus_read(cpu_read_reg(ctx.cur_inst.reg_2)) as u16;
             emu_cycles(1);
-            cpu_set_reg(RegType::RT_HL, cpu_read_reg(RegType::RT_HL) - 1);
+            cpu_set_reg(RegType::RT_HL, cpu_read_reg(RegType::RT_HL).wrapping_sub(1));
         }
         AddrMode::AM_HLI_R => {
             ctx.fetched_data = cpu_read_reg(ctx.cur_inst.reg_2);
             ctx.mem_dest = cpu_read_reg(ctx.cur_inst.reg_1);
             ctx.dest_is_mem = true;
-            cpu_set_reg(RegType::RT_HL, cpu_read_reg(RegType::RT_HL) + 1);
+            cpu_set_reg(RegType::RT_HL, cpu_read_reg(RegType::RT_HL).wrapping_add(1));
         }
         AddrMode::AM_HLD_R => {
             ctx.fetched_data = cpu_read_reg(ctx.cur_inst.reg_2);
             ctx.mem_dest = cpu_read_reg(ctx.cur_inst.reg_1);
             ctx.dest_is_mem = true;
-            cpu_set_reg(RegType::RT_HL, cpu_read_reg(RegType::RT_HL) - 1);
+            cpu_set_reg(RegType::RT_HL, cpu_read_reg(RegType::RT_HL).wrapping_sub(1));
         }
         AddrMode::AM_R_A8 => {
             ctx.fetched_data = bus_read(ctx.regs.pc) as u16;
@@ -1,10 +1,15 @@
 use super::bus::{bus_read, bus_write, bus_write16};
 use super::common::bit_set;
 use super::cpu::CpuContext;
-use super::cpu_util::{cpu_flag_c, cpu_flag_z, cpu_read_reg, cpu_set_reg};
-use super::emu::emu_cycles;
-use super::instructions::{AddrMode, CondType, InType, RegType};
+use super::cpu_util::{
+    cpu_flag_c, cpu_flag_h, cpu_flag_n, cpu_flag_z, cpu_read_reg, cpu_read_reg8, cpu_set_reg,
+    cpu_set_reg8,
+};
+use super::emu::{emu_cycles, emu_speed_switch};
+use super::instructions::{cb_instruction_by_opcode, AddrMode, CondType, InType, RegType};
+use super::interrupts::interrupt_pending;
 use super::stack::{stack_pop, stack_push, stack_push16};
+use super::timer::timer_write;
 
 pub type InProc = unsafe fn(&mut CpuContext);
 
@@ -20,16 +25,62 @@ fn proc_nop(ctx: &mut CpuContext) {}
 
 unsafe fn proc_di(ctx: &mut CpuContext) {
     ctx.int_master_enabled = false;
+    ctx.enabling_ime = false;
+}
+
+unsafe fn proc_ei(ctx: &mut CpuContext) {
+    ctx.enabling_ime = true;
+}
+
+/*
+    HALT with an interrupt already pending (IE & IF != 0) never sleeps:
+
+    IME=1: the interrupt is serviced straight away, unless HALT follows EI,
+           in which case the handler returns to the HALT itself
+    IME=0: the CPU keeps running but fails to increment PC on the next
+           opcode fetch (the "HALT bug")
+*/
+fn proc_halt(ctx: &mut CpuContext) {
+    if interrupt_pending(ctx) == 0 {
+        ctx.halted = true;
+    } else if ctx.enabling_ime {
+        ctx.regs.pc = ctx.regs.pc.wrapping_sub(1);
+    } else if !ctx.int_master_enabled {
+        ctx.halt_bug = true;
+    }
+}
+
+/*
+    STOP resets DIV and, with a CGB speed switch armed, performs the switch
+    instead of stopping.
+
+    Approximated: the CPU otherwise sleeps like HALT and wakes on any
+    enabled interrupt, where real hardware also stops the LCD and timer and
+    only wakes when a selected joypad line goes low.
+*/
+unsafe fn proc_stop(ctx: &mut CpuContext) {
+    // STOP is encoded as 10 00, the second byte is skipped
+    ctx.regs.pc = ctx.regs.pc.wrapping_add(1);
+
+    timer_write(0xFF04, 0);
+
+    if !emu_speed_switch() {
+        ctx.halted = true;
+    }
 }
 
 unsafe fn proc_ld(ctx: &mut CpuContext) {
     if ctx.dest_is_mem {
+        //LD (BC), A for instance...
         if ctx.cur_inst.reg_2.is_16bit() {
             emu_cycles(1);
             bus_write16(ctx.mem_dest, ctx.fetched_data);
         } else {
             bus_write(ctx.mem_dest, ctx.fetched_data as u8);
         }
+
+        emu_cycles(1);
+        return;
     }
 
     if ctx.cur_inst.mode == AddrMode::AM_HL_SPR {
@@ -40,9 +91,16 @@ unsafe fn proc_ld(ctx: &mut CpuContext) {
 
         cpu_set_reg(
             ctx.cur_inst.reg_1,
-            cpu_read_reg(ctx.cur_inst.reg_2) + (ctx.fetched_data as u8) as u16,
-        )
+            cpu_read_reg(ctx.cur_inst.reg_2).wrapping_add(ctx.fetched_data as u8 as i8 as u16),
+        );
+        emu_cycles(1);
+        return;
+    }
+
+    if ctx.cur_inst.reg_1 == RegType::RT_SP && ctx.cur_inst.reg_2 == RegType::RT_HL {
+        emu_cycles(1);
     }
+
     cpu_set_reg(ctx.cur_inst.reg_1, ctx.fetched_data);
 }
 
@@ -77,12 +135,24 @@ unsafe fn proc_ldh(ctx: &mut CpuContext) {
             bus_read(0xFF00 | ctx.fetched_data) as u16,
         );
     } else {
-        bus_write(0xFF00 | ctx.fetched_data, ctx.regs.a);
+        bus_write(ctx.mem_dest, ctx.regs.a);
     }
 
     emu_cycles(1);
 }
 
+fn proc_and(ctx: &mut CpuContext) {
+    ctx.regs.a &= ctx.fetched_data as u8;
+
+    cpu_set_flags(
+        ctx,
+        Some(ctx.regs.a == 0),
+        Some(false),
+        Some(true),
+        Some(false),
+    )
+}
+
 fn proc_xor(ctx: &mut CpuContext) {
     ctx.regs.a ^= ctx.fetched_data as u8;
 
@@ -95,6 +165,273 @@ fn proc_xor(ctx: &mut CpuContext) {
     )
 }
 
+fn proc_or(ctx: &mut CpuContext) {
+    ctx.regs.a |= ctx.fetched_data as u8;
+
+    cpu_set_flags(
+        ctx,
+        Some(ctx.regs.a == 0),
+        Some(false),
+        Some(false),
+        Some(false),
+    )
+}
+
+fn proc_cp(ctx: &mut CpuContext) {
+    let a = ctx.regs.a as i32;
+    let u = (ctx.fetched_data & 0xFF) as i32;
+    let n = a - u;
+
+    cpu_set_flags(
+        ctx,
+        Some(n == 0),
+        Some(true),
+        Some((a & 0x0F) - (u & 0x0F) < 0),
+        Some(n < 0),
+    )
+}
+
+unsafe fn proc_inc(ctx: &mut CpuContext) {
+    let reg = ctx.cur_inst.reg_1;
+
+    if reg.is_16bit() && ctx.cur_inst.mode != AddrMode::AM_MR {
+        // INC rr leaves the flags untouched and takes an extra cycle
+        emu_cycles(1);
+        cpu_set_reg(reg, cpu_read_reg(reg).wrapping_add(1));
+        return;
+    }
+
+    let val = if ctx.cur_inst.mode == AddrMode::AM_MR {
+        let val = (ctx.fetched_data as u8).wrapping_add(1);
+        bus_write(ctx.mem_dest, val);
+        emu_cycles(1);
+        val
+    } else {
+        let val = (cpu_read_reg(reg) as u8).wrapping_add(1);
+        cpu_set_reg(reg, val as u16);
+        val
+    };
+
+    cpu_set_flags(ctx, Some(val == 0), Some(false), Some(val & 0x0F == 0), None);
+}
+
+unsafe fn proc_dec(ctx: &mut CpuContext) {
+    let reg = ctx.cur_inst.reg_1;
+
+    if reg.is_16bit() && ctx.cur_inst.mode != AddrMode::AM_MR {
+        // DEC rr leaves the flags untouched and takes an extra cycle
+        emu_cycles(1);
+        cpu_set_reg(reg, cpu_read_reg(reg).wrapping_sub(1));
+        return;
+    }
+
+    let val = if ctx.cur_inst.mode == AddrMode::AM_MR {
+        let val = (ctx.fetched_data as u8).wrapping_sub(1);
+        bus_write(ctx.mem_dest, val);
+        emu_cycles(1);
+        val
+    } else {
+        let val = (cpu_read_reg(reg) as u8).wrapping_sub(1);
+        cpu_set_reg(reg, val as u16);
+        val
+    };
+
+    cpu_set_flags(ctx, Some(val == 0), Some(true), Some(val & 0x0F == 0x0F), None);
+}
+
+unsafe fn proc_add(ctx: &mut CpuContext) {
+    let reg = ctx.cur_inst.reg_1;
+    let a = cpu_read_reg(reg) as u32;
+    let u = ctx.fetched_data as u32;
+
+    if reg == RegType::RT_SP {
+        // ADD SP, r8: flags come from the unsigned low byte addition
+        let h = (a & 0xF) + (u & 0xF) >= 0x10;
+        let c = (a & 0xFF) + (u & 0xFF) >= 0x100;
+
+        emu_cycles(2);
+        cpu_set_reg(reg, (a as u16).wrapping_add(u as u8 as i8 as u16));
+        cpu_set_flags(ctx, Some(false), Some(false), Some(h), Some(c));
+        return;
+    }
+
+    if reg.is_16bit() {
+        let h = (a & 0xFFF) + (u & 0xFFF) >= 0x1000;
+        let c = a + u >= 0x10000;
+
+        emu_cycles(1);
+        cpu_set_reg(reg, (a + u) as u16);
+        cpu_set_flags(ctx, None, Some(false), Some(h), Some(c));
+        return;
+    }
+
+    let u = u & 0xFF;
+    let val = a + u;
+    let h = (a & 0xF) + (u & 0xF) >= 0x10;
+    let c = val >= 0x100;
+
+    cpu_set_reg(reg, (val & 0xFF) as u16);
+    cpu_set_flags(ctx, Some(val & 0xFF == 0), Some(false), Some(h), Some(c));
+}
+
+unsafe fn proc_adc(ctx: &mut CpuContext) {
+    let a = ctx.regs.a as u16;
+    let u = ctx.fetched_data & 0xFF;
+    let c = cpu_flag_c() as u16;
+    let val = a + u + c;
+
+    ctx.regs.a = val as u8;
+
+    cpu_set_flags(
+        ctx,
+        Some(val & 0xFF == 0),
+        Some(false),
+        Some((a & 0xF) + (u & 0xF) + c > 0xF),
+        Some(val > 0xFF),
+    )
+}
+
+fn proc_sub(ctx: &mut CpuContext) {
+    let a = ctx.regs.a as i32;
+    let u = (ctx.fetched_data & 0xFF) as i32;
+    let val = a - u;
+
+    ctx.regs.a = val as u8;
+
+    cpu_set_flags(
+        ctx,
+        Some(val == 0),
+        Some(true),
+        Some((a & 0xF) - (u & 0xF) < 0),
+        Some(val < 0),
+    )
+}
+
+unsafe fn proc_sbc(ctx: &mut CpuContext) {
+    let a = ctx.regs.a as i32;
+    let u = (ctx.fetched_data & 0xFF) as i32;
+    let c = cpu_flag_c() as i32;
+    let val = a - u - c;
+
+    ctx.regs.a = val as u8;
+
+    cpu_set_flags(
+        ctx,
+        Some(val & 0xFF == 0),
+        Some(true),
+        Some((a & 0xF) - (u & 0xF) - c < 0),
+        Some(val < 0),
+    )
+}
+
+fn proc_rlca(ctx: &mut CpuContext) {
+    let c = ctx.regs.a >> 7;
+    ctx.regs.a = (ctx.regs.a << 1) | c;
+
+    cpu_set_flags(ctx, Some(false), Some(false), Some(false), Some(c == 1));
+}
+
+fn proc_rrca(ctx: &mut CpuContext) {
+    let c = ctx.regs.a & 1;
+    ctx.regs.a = (ctx.regs.a >> 1) | (c << 7);
+
+    cpu_set_flags(ctx, Some(false), Some(false), Some(false), Some(c == 1));
+}
+
+unsafe fn proc_rla(ctx: &mut CpuContext) {
+    let cf = cpu_flag_c() as u8;
+    let c = ctx.regs.a >> 7;
+    ctx.regs.a = (ctx.regs.a << 1) | cf;
+
+    cpu_set_flags(ctx, Some(false), Some(false), Some(false), Some(c == 1));
+}
+
+unsafe fn proc_rra(ctx: &mut CpuContext) {
+    let cf = cpu_flag_c() as u8;
+    let c = ctx.regs.a & 1;
+    ctx.regs.a = (ctx.regs.a >> 1) | (cf << 7);
+
+    cpu_set_flags(ctx, Some(false), Some(false), Some(false), Some(c == 1));
+}
+
+unsafe fn proc_daa(ctx: &mut CpuContext) {
+    let mut u: u8 = 0;
+    let mut fc = false;
+
+    if cpu_flag_h() || (!cpu_flag_n() && (ctx.regs.a & 0xF) > 9) {
+        u = 6;
+    }
+
+    if cpu_flag_c() || (!cpu_flag_n() && ctx.regs.a > 0x99) {
+        u |= 0x60;
+        fc = true;
+    }
+
+    ctx.regs.a = if cpu_flag_n() {
+        ctx.regs.a.wrapping_sub(u)
+    } else {
+        ctx.regs.a.wrapping_add(u)
+    };
+
+    cpu_set_flags(ctx, Some(ctx.regs.a == 0), None, Some(false), Some(fc));
+}
+
+fn proc_cpl(ctx: &mut CpuContext) {
+    ctx.regs.a = !ctx.regs.a;
+    cpu_set_flags(ctx, None, Some(true), Some(true), None);
+}
+
+fn proc_scf(ctx: &mut CpuContext) {
+    cpu_set_flags(ctx, None, Some(false), Some(false), Some(true));
+}
+
+unsafe fn proc_ccf(ctx: &mut CpuContext) {
+    let c = cpu_flag_c();
+    cpu_set_flags(ctx, None, Some(false), Some(false), Some(!c));
+}
+
+unsafe fn proc_cb(ctx: &mut CpuContext) {
+    let inst = cb_instruction_by_opcode(ctx.fetched_data as u8);
+    let reg = inst.reg_1;
+    let bit = inst.param;
+    let is_mem = inst.mode == AddrMode::AM_MR;
+
+    let val = cpu_read_reg8(reg);
+    if is_mem {
+        emu_cycles(1);
+    }
+
+    let flag_c = cpu_flag_c() as u8;
+
+    let (result, c) = match inst.i_type {
+        InType::IN_BIT => {
+            cpu_set_flags(ctx, Some(val & (1 << bit) == 0), Some(false), Some(true), None);
+            return;
+        }
+        InType::IN_RES => (val & !(1 << bit), None),
+        InType::IN_SET => (val | (1 << bit), None),
+        InType::IN_RLC => (val.rotate_left(1), Some(val >> 7 == 1)),
+        InType::IN_RRC => (val.rotate_right(1), Some(val & 1 == 1)),
+        InType::IN_RL => ((val << 1) | flag_c, Some(val >> 7 == 1)),
+        InType::IN_RR => ((val >> 1) | (flag_c << 7), Some(val & 1 == 1)),
+        InType::IN_SLA => (val << 1, Some(val >> 7 == 1)),
+        InType::IN_SRA => (((val as i8) >> 1) as u8, Some(val & 1 == 1)),
+        InType::IN_SWAP => (val.rotate_left(4), Some(false)),
+        InType::IN_SRL => (val >> 1, Some(val & 1 == 1)),
+        _ => panic!("Invalid CB instruction: {:02X}", ctx.fetched_data),
+    };
+
+    cpu_set_reg8(reg, result);
+    if is_mem {
+        emu_cycles(1);
+    }
+
+    // RES and SET leave the flags untouched
+    if c.is_some() {
+        cpu_set_flags(ctx, Some(result == 0), Some(false), Some(false), c);
+    }
+}
+
 unsafe fn check_cond(ctx: &mut CpuContext) -> bool {
     let z = cpu_flag_z();
     let c = cpu_flag_c();
@@ -124,9 +461,14 @@ unsafe fn proc_jp(ctx: &mut CpuContext) {
     goto_addr(ctx, ctx.fetched_data, false);
 }
 
+fn proc_jphl(ctx: &mut CpuContext) {
+    // JP HL does not spend a cycle loading the new PC
+    ctx.regs.pc = ctx.fetched_data;
+}
+
 unsafe fn proc_jr(ctx: &mut CpuContext) {
-    let rel = (ctx.fetched_data as u8) as u16;
-    let addr = ctx.regs.pc + rel;
+    let rel = (ctx.fetched_data as u8) as i8;
+    let addr = ctx.regs.pc.wrapping_add(rel as u16);
     goto_addr(ctx, addr, false);
 }
 
@@ -195,16 +537,38 @@ pub fn inst_get_processor(i_type: InType) -> InProc {
         InType::IN_NOP => proc_nop,
         InType::IN_LDH => proc_ldh,
         InType::IN_LD => proc_ld,
+        InType::IN_INC => proc_inc,
+        InType::IN_DEC => proc_dec,
+        InType::IN_ADD => proc_add,
+        InType::IN_ADC => proc_adc,
+        InType::IN_SUB => proc_sub,
+        InType::IN_SBC => proc_sbc,
+        InType::IN_AND => proc_and,
+        InType::IN_XOR => proc_xor,
+        InType::IN_OR => proc_or,
+        InType::IN_CP => proc_cp,
+        InType::IN_RLCA => proc_rlca,
+        InType::IN_RRCA => proc_rrca,
+        InType::IN_RLA => proc_rla,
+        InType::IN_RRA => proc_rra,
+        InType::IN_DAA => proc_daa,
+        InType::IN_CPL => proc_cpl,
+        InType::IN_SCF => proc_scf,
+        InType::IN_CCF => proc_ccf,
         InType::IN_JP => proc_jp,
+        InType::IN_JPHL => proc_jphl,
         InType::IN_POP => proc_pop,
         InType::IN_PUSH => proc_push,
         InType::IN_CALL => proc_call,
         InType::IN_JR => proc_jr,
         InType::IN_DI => proc_di,
+        InType::IN_EI => proc_ei,
+        InType::IN_HALT => proc_halt,
+        InType::IN_STOP => proc_stop,
         InType::IN_RET => proc_ret,
         InType::IN_RETI => proc_reti,
-        InType::IN_XOR => proc_xor,
         InType::IN_RST => proc_rst,
+        InType::IN_CB => proc_cb,
         _ => proc_unknown,
     }
 }
@@ -1,3 +1,4 @@
+use super::bus::{bus_read, bus_write};
 use super::common::bit;
 use super::cpu::{cpu_get_context, CpuRegisters};
 use super::instructions::RegType;
@@ -7,6 +8,16 @@ pub unsafe fn cpu_flag_z() -> bool {
     bit(ctx.regs.f, 7)
 }
 
+pub unsafe fn cpu_flag_n() -> bool {
+    let ctx = cpu_get_context();
+    bit(ctx.regs.f, 6)
+}
+
+pub unsafe fn cpu_flag_h() -> bool {
+    let ctx = cpu_get_context();
+    bit(ctx.regs.f, 5)
+}
+
 pub unsafe fn cpu_flag_c() -> bool {
     let ctx = cpu_get_context();
     bit(ctx.regs.f, 4)
@@ -74,6 +85,21 @@ pub unsafe fn cpu_set_reg(rt: RegType, val: u16) {
     }
 }
 
+// 8-bit operand access where RT_HL means the byte at (HL), as used by the CB page
+pub unsafe fn cpu_read_reg8(rt: RegType) -> u8 {
+    match rt {
+        RegType::RT_HL => bus_read(cpu_read_reg(RegType::RT_HL)),
+        _ => cpu_read_reg(rt) as u8,
+    }
+}
+
+pub unsafe fn cpu_set_reg8(rt: RegType, val: u8) {
+    match rt {
+        RegType::RT_HL => bus_write(cpu_read_reg(RegType::RT_HL), val),
+        _ => cpu_set_reg(rt, val as u16),
+    }
+}
+
 pub unsafe fn cpu_get_regs() -> &'static mut CpuRegisters {
     let ctx = cpu_get_context();
     &mut ctx.regs
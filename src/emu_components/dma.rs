@@ -0,0 +1,64 @@
+use super::bus::bus_read_direct;
+use super::ppu::ppu_oam_dma_write;
+
+/*
+  OAM DMA, started by writing the source page to 0xFF46.
+
+  After a 1 M-cycle setup delay one byte is copied from XX00-XX9F into
+  OAM every M-cycle, 160 M-cycles in total. While the copy runs the CPU
+  can only reach HRAM (and the on-chip registers), so games run their
+  DMA routine from HRAM.
+*/
+
+#[derive(Debug)]
+pub struct DmaContext {
+    pub active: bool,
+    pub byte: u8,
+    pub value: u8,
+    pub start_delay: u8,
+}
+
+static mut CTX: DmaContext = DmaContext {
+    active: false,
+    byte: 0,
+    value: 0,
+    start_delay: 0,
+};
+
+pub unsafe fn dma_init() {
+    CTX.active = false;
+    CTX.byte = 0;
+    CTX.value = 0;
+    CTX.start_delay = 0;
+}
+
+pub unsafe fn dma_start(start: u8) {
+    // Sources above 0xDF read the WRAM echo instead of OAM/IO
+    let start = if start >= 0xE0 { start - 0x20 } else { start };
+
+    CTX.active = true;
+    CTX.byte = 0;
+    CTX.value = start;
+    CTX.start_delay = 1;
+}
+
+pub unsafe fn dma_tick() {
+    if !CTX.active {
+        return;
+    }
+
+    if CTX.start_delay > 0 {
+        CTX.start_delay -= 1;
+        return;
+    }
+
+    let address = ((CTX.value as u16) << 8) | CTX.byte as u16;
+    ppu_oam_dma_write(CTX.byte, bus_read_direct(address));
+
+    CTX.byte += 1;
+    CTX.active = CTX.byte < 0xA0;
+}
+
+pub unsafe fn dma_transferring() -> bool {
+    CTX.active && CTX.start_delay == 0
+}
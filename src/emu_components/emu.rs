@@ -1,6 +1,20 @@
-use super::cart::cart_load;
+use super::apu::{apu_init, apu_tick};
+use super::boot::{boot_load, boot_rom_cgb, boot_rom_mapped};
+use super::cart::{cart_battery_save, cart_battery_tick, cart_is_cgb, cart_load, CartOptions};
 use super::cpu::{cpu_init, cpu_step};
+use super::dma::{dma_init, dma_tick};
+use super::info::info_run;
+use super::joypad::joypad_init;
+use super::lcd::lcd_init;
+use super::model::HardwareModel;
+use super::ppu::{
+    ppu_init, ppu_set_renderer, ppu_tick, PpuRenderer, LINES_PER_FRAME, TICKS_PER_LINE,
+};
+use super::serial::{serial_init, serial_tick};
+use super::timer::{timer_init, timer_tick};
 use sdl2;
+use sdl2::event::Event;
+use sdl2::EventPump;
 use sdl2_sys::SDL_Delay;
 
 #[derive(Debug)]
@@ -8,6 +22,10 @@ pub struct EmuContext {
     paused: bool,
     running: bool,
     ticks: u64,
+    model: HardwareModel,
+    cgb_mode: bool,
+    double_speed: bool,
+    speed_switch_armed: bool,
 }
 
 /*
@@ -18,6 +36,9 @@ pub struct EmuContext {
   |Address Bus|
   |PPU|
   |Timer|
+  |DMA|
+  |Serial|
+  |APU|
 
 */
 
@@ -25,6 +46,10 @@ static mut CTX: EmuContext = EmuContext {
     paused: false,
     running: false,
     ticks: 0,
+    model: HardwareModel::DMG,
+    cgb_mode: false,
+    double_speed: false,
+    speed_switch_armed: false,
 };
 
 pub unsafe fn emu_get_context() -> &'static mut EmuContext {
@@ -35,29 +60,135 @@ unsafe fn delay(ms: u32) {
     SDL_Delay(ms);
 }
 
+// SDL turns SIGINT and SIGTERM into a quit event once its event subsystem is up
+unsafe fn handle_events(event_pump: &mut EventPump) {
+    for event in event_pump.poll_iter() {
+        if let Event::Quit { .. } = event {
+            CTX.running = false;
+        }
+    }
+}
+
 pub unsafe fn emu_run(args: Vec<String>) {
-    if args.len() < 2 {
-        println!("Usage: emu <rom_file>");
+    // emu info only inspects headers, SDL is never initialised
+    if args.len() > 1 && args[1] == "info" {
+        if !info_run(&args[2..]) {
+            std::process::exit(1);
+        }
         return;
     }
 
-    let rom_file = &args[1];
-    cart_load(rom_file.to_owned()).expect("Failed to load cart");
+    // --lenient loads ROMs with a bad header, homebrew often ships without valid checksums
+    let options = CartOptions {
+        lenient: args.iter().any(|arg| arg == "--lenient"),
+        zip_entry: args
+            .iter()
+            .find_map(|arg| arg.strip_prefix("--entry="))
+            .map(|entry| entry.to_string()),
+        patches: args
+            .iter()
+            .filter_map(|arg| arg.strip_prefix("--patch="))
+            .map(|patch| patch.to_string())
+            .collect(),
+    };
+
+    let rom_file = match args.iter().skip(1).find(|arg| !arg.starts_with("--")) {
+        Some(rom_file) => rom_file,
+        None => {
+            println!("Usage: emu [options] <rom_file>");
+            println!("       emu info [--json] <rom_file>...");
+            println!();
+            println!("  --lenient              load ROMs with a bad header");
+            println!("  --entry=<name>         zip entry to load");
+            println!("  --patch=<file>         IPS/UPS/BPS patch, may be repeated");
+            println!("  --boot=<file>          DMG/MGB/SGB or CGB boot ROM image");
+            println!("  --model=<model>        dmg0, dmg, mgb, sgb, sgb2, cgb or agb");
+            println!("  --renderer=<name>      fifo (default) or scanline");
+            return;
+        }
+    };
+
+    if let Err(e) = cart_load(rom_file.to_owned(), &options) {
+        println!("Failed to load cart: {}", e);
+        return;
+    }
 
     println!("Cart loaded..");
 
-    let _ = sdl2::init().expect("Expecting SDL2 to work");
+    let model = match args.iter().find_map(|arg| arg.strip_prefix("--model=")) {
+        Some(name) => match HardwareModel::from_name(name) {
+            Some(model) => Some(model),
+            None => {
+                println!("Unknown hardware model: {}", name);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    if let Some(name) = args.iter().find_map(|arg| arg.strip_prefix("--renderer=")) {
+        match PpuRenderer::from_name(name) {
+            Some(renderer) => ppu_set_renderer(renderer),
+            None => {
+                println!("Unknown renderer: {}", name);
+                return;
+            }
+        }
+    }
+
+    if let Some(boot_file) = args.iter().find_map(|arg| arg.strip_prefix("--boot=")) {
+        if let Err(e) = boot_load(boot_file, model) {
+            println!("Failed to load boot ROM: {}", e);
+            return;
+        }
+    }
+
+    // Without --model the boot ROM, or else the cartridge, picks DMG or CGB hardware
+    CTX.model = match model {
+        Some(model) => model,
+        None if boot_rom_mapped() && boot_rom_cgb() => HardwareModel::CGB,
+        None if !boot_rom_mapped() && cart_is_cgb() => HardwareModel::CGB,
+        None => HardwareModel::DMG,
+    };
+
+    println!("Model: {:?}", CTX.model);
+
+    // A CGB boot ROM always starts in CGB mode and picks DMG compatibility itself
+    CTX.cgb_mode = if boot_rom_mapped() {
+        CTX.model.is_cgb()
+    } else {
+        CTX.model.is_cgb() && cart_is_cgb()
+    };
+
+    let sdl = sdl2::init().expect("Expecting SDL2 to work");
+    let mut event_pump = sdl.event_pump().expect("Expecting SDL2 events to work");
     println!("SDL INIT");
     let _ = sdl2::ttf::init().expect("Expecting SDL2 ttf to work");
     println!("TTF INIT");
 
     cpu_init();
+    timer_init();
+    lcd_init();
+    ppu_init();
+    dma_init();
+    joypad_init();
+    serial_init();
+    apu_init();
 
     CTX.running = true;
     CTX.paused = false;
     CTX.ticks = 0;
 
+    let frame_ticks = LINES_PER_FRAME as u64 * TICKS_PER_LINE as u64;
+    let mut next_events: u64 = 0;
+
     while CTX.running {
+        // Events are polled about once per frame of emulated time
+        if CTX.paused || CTX.ticks >= next_events {
+            handle_events(&mut event_pump);
+            next_events = CTX.ticks + frame_ticks;
+        }
+
         if CTX.paused {
             delay(10);
             continue;
@@ -65,13 +196,73 @@ pub unsafe fn emu_run(args: Vec<String>) {
 
         if !cpu_step() {
             println!("CPU Stopped");
-            return;
+            break;
         }
 
-        CTX.ticks += 1;
+        cart_battery_tick();
+    }
+
+    if let Err(e) = cart_battery_save() {
+        println!("Failed to write save: {}", e);
+    }
+}
+
+pub unsafe fn emu_model() -> HardwareModel {
+    CTX.model
+}
+
+pub unsafe fn emu_cgb_mode() -> bool {
+    CTX.cgb_mode
+}
+
+// Set by the CGB boot ROM when it leaves a DMG cartridge in compatibility mode
+pub unsafe fn emu_set_cgb_mode(on: bool) {
+    CTX.cgb_mode = on;
+}
+
+// 0xFF4D KEY1: bit 7 current speed, bit 0 switch armed
+pub unsafe fn emu_key1_read() -> u8 {
+    0x7E | ((CTX.double_speed as u8) << 7) | (CTX.speed_switch_armed as u8)
+}
+
+pub unsafe fn emu_key1_write(value: u8) {
+    CTX.speed_switch_armed = value & 1 == 1;
+}
+
+// Called by STOP, returns true if an armed speed switch was performed instead of stopping
+pub unsafe fn emu_speed_switch() -> bool {
+    if !CTX.cgb_mode || !CTX.speed_switch_armed {
+        return false;
     }
+
+    CTX.speed_switch_armed = false;
+    CTX.double_speed = !CTX.double_speed;
+    true
 }
 
-pub fn emu_cycles(cpu_cycles: i32) {
-    // todo
+/*
+  Advances everything except the CPU by the given number of M-cycles.
+
+  The timer, DMA and serial port are clocked with the CPU, so they see
+  4 T-cycles per M-cycle at either speed. The PPU and APU run off the
+  fixed 4 MiHz clock and only see 2 dots per M-cycle in CGB double speed.
+  ticks counts those fixed-clock dots.
+*/
+pub unsafe fn emu_cycles(cpu_cycles: i32) {
+    let dots = if CTX.double_speed { 2 } else { 4 };
+
+    for _ in 0..cpu_cycles {
+        for _ in 0..4 {
+            timer_tick();
+        }
+
+        for _ in 0..dots {
+            CTX.ticks += 1;
+            ppu_tick();
+            apu_tick();
+        }
+
+        dma_tick();
+        serial_tick();
+    }
 }
@@ -0,0 +1,171 @@
+use super::archive::rom_file_read;
+use super::cart_header::{global_checksum, header_checksum, RomHeader};
+use sha1::{Digest, Sha1};
+
+/*
+  emu info [--json] <rom_file>...
+
+  Prints the parsed header of every ROM given, plain or compressed,
+  without booting it or touching SDL. Headers are parsed leniently so
+  that every problem is listed instead of stopping at the first one.
+  --json prints an array with one object per file.
+*/
+
+struct RomInfo {
+    file: String,
+    header: RomHeader,
+    problems: Vec<String>,
+    rom: Vec<u8>,
+    header_checksum_ok: bool,
+    global_checksum_ok: bool,
+    crc32: u32,
+    sha1: String,
+}
+
+fn rom_info(file: &str) -> Result<RomInfo, String> {
+    let rom = rom_file_read(file, None).map_err(|e| e.to_string())?.data;
+    let header = RomHeader::parse(&rom, true).map_err(|e| e.to_string())?;
+
+    let sha1 = Sha1::digest(&rom)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+
+    Ok(RomInfo {
+        file: file.to_string(),
+        problems: header.check(&rom).iter().map(|e| e.to_string()).collect(),
+        header_checksum_ok: header_checksum(&rom) == header.checksum,
+        global_checksum_ok: global_checksum(&rom) == header.global_checksum,
+        crc32: crc32fast::hash(&rom),
+        sha1,
+        header,
+        rom,
+    })
+}
+
+fn print_text(info: &RomInfo) {
+    let mut summary = String::new();
+    // Writing to a String cannot fail
+    let _ = info.header.write_summary(&mut summary, &info.rom);
+
+    println!("{}:", info.file);
+    print!("{}", summary);
+    println!("\t CRC32    : {:08x}", info.crc32);
+    println!("\t SHA-1    : {}", info.sha1);
+
+    for problem in &info.problems {
+        println!("\t Warning  : {}", problem);
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+fn json_option(value: Option<String>) -> String {
+    match value {
+        Some(value) => json_string(&value),
+        None => "null".to_string(),
+    }
+}
+
+fn json_object(info: &RomInfo) -> String {
+    let header = &info.header;
+    let problems: Vec<String> = info.problems.iter().map(|p| json_string(p)).collect();
+
+    let fields = [
+        ("file", json_string(&info.file)),
+        ("title", json_string(&header.title())),
+        ("manufacturer_code", json_option(header.manufacturer_code())),
+        ("type", header.c_type.to_string()),
+        (
+            "type_name",
+            json_option(header.rom_type().map(|name| name.to_string())),
+        ),
+        (
+            "rom_size",
+            match header.rom_size_bytes() {
+                Some(size) => size.to_string(),
+                None => "null".to_string(),
+            },
+        ),
+        ("file_size", info.rom.len().to_string()),
+        ("ram_size_kib", header.ram_size_kib().to_string()),
+        ("licensee_code", json_option(header.new_licensee_code())),
+        ("old_licensee_code", header.lic_code.to_string()),
+        ("licensee", json_string(&header.licensee())),
+        ("cgb", json_string(&format!("{:?}", header.cgb_support()))),
+        ("sgb", header.sgb_support().to_string()),
+        (
+            "destination",
+            json_string(&format!("{:?}", header.destination())),
+        ),
+        ("version", header.version.to_string()),
+        ("header_checksum", header.checksum.to_string()),
+        ("header_checksum_ok", info.header_checksum_ok.to_string()),
+        ("global_checksum", header.global_checksum.to_string()),
+        ("global_checksum_ok", info.global_checksum_ok.to_string()),
+        ("crc32", json_string(&format!("{:08x}", info.crc32))),
+        ("sha1", json_string(&info.sha1)),
+        ("warnings", format!("[{}]", problems.join(", "))),
+    ];
+
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("    \"{}\": {}", key, value))
+        .collect();
+
+    format!("  {{\n{}\n  }}", fields.join(",\n"))
+}
+
+// Returns false if any of the files could not be read or parsed
+pub fn info_run(args: &[String]) -> bool {
+    let json = args.iter().any(|arg| arg == "--json");
+    let files: Vec<&String> = args.iter().filter(|arg| !arg.starts_with("--")).collect();
+
+    if files.is_empty() {
+        println!("Usage: emu info [--json] <rom_file>...");
+        return false;
+    }
+
+    let mut ok = true;
+    let mut objects = Vec::new();
+
+    for file in files {
+        match rom_info(file) {
+            Ok(info) if json => objects.push(json_object(&info)),
+            Ok(info) => print_text(&info),
+            Err(e) => {
+                ok = false;
+
+                if json {
+                    objects.push(format!(
+                        "  {{\n    \"file\": {},\n    \"error\": {}\n  }}",
+                        json_string(file),
+                        json_string(&e)
+                    ));
+                } else {
+                    println!("{}: {}", file, e);
+                }
+            }
+        }
+    }
+
+    if json {
+        println!("[\n{}\n]", objects.join(",\n"));
+    }
+
+    ok
+}
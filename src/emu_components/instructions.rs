@@ -148,7 +148,76 @@ static mut INSTRUCTIONS: [Instruction; 0x100] = [Instruction {
     param: 0,
 }; 0x100];
 
+static mut CB_INSTRUCTIONS: [Instruction; 0x100] = [Instruction {
+    i_type: InType::IN_NONE,
+    mode: AddrMode::AM_IMP,
+    reg_1: RegType::RT_NONE,
+    reg_2: RegType::RT_NONE,
+    cond: CondType::CT_NONE,
+    param: 0,
+}; 0x100];
+
+// Operand order used by the low three bits of a CB opcode
+static CB_REGS: [RegType; 8] = [
+    RegType::RT_B,
+    RegType::RT_C,
+    RegType::RT_D,
+    RegType::RT_E,
+    RegType::RT_H,
+    RegType::RT_L,
+    RegType::RT_HL,
+    RegType::RT_A,
+];
+
+// Shift/rotate group selected by bits 3-5 when bits 6-7 are zero
+static CB_SHIFTS: [InType; 8] = [
+    InType::IN_RLC,
+    InType::IN_RRC,
+    InType::IN_RL,
+    InType::IN_RR,
+    InType::IN_SLA,
+    InType::IN_SRA,
+    InType::IN_SWAP,
+    InType::IN_SRL,
+];
+
+/*
+    CB opcode layout:
+
+    bits 7-6: 00 shift/rotate, 01 BIT, 10 RES, 11 SET
+    bits 5-3: shift/rotate kind or bit index
+    bits 2-0: operand (B, C, D, E, H, L, (HL), A)
+*/
+unsafe fn set_cb_instructions() {
+    for op in 0..0x100 {
+        let reg = CB_REGS[op & 0b111];
+        let bit = ((op >> 3) & 0b111) as u8;
+
+        let i_type = match op >> 6 {
+            0 => CB_SHIFTS[bit as usize],
+            1 => InType::IN_BIT,
+            2 => InType::IN_RES,
+            _ => InType::IN_SET,
+        };
+
+        CB_INSTRUCTIONS[op] = Instruction {
+            i_type,
+            mode: if reg == RegType::RT_HL {
+                AddrMode::AM_MR
+            } else {
+                AddrMode::AM_R
+            },
+            reg_1: reg,
+            reg_2: RegType::RT_NONE,
+            cond: CondType::CT_NONE,
+            param: if op >> 6 == 0 { 0 } else { bit },
+        };
+    }
+}
+
 pub unsafe fn set_instructions() {
+    set_cb_instructions();
+
     INSTRUCTIONS[0x00] = Instruction {
         i_type: InType::IN_NOP,
         mode: AddrMode::AM_IMP,
@@ -173,7 +242,22 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
-
+    INSTRUCTIONS[0x03] = Instruction {
+        i_type: InType::IN_INC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_BC,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x04] = Instruction {
+        i_type: InType::IN_INC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_B,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x05] = Instruction {
         i_type: InType::IN_DEC,
         mode: AddrMode::AM_R,
@@ -190,7 +274,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
-
+    INSTRUCTIONS[0x07] = Instruction {
+        i_type: InType::IN_RLCA,
+        mode: AddrMode::AM_IMP,
+        reg_1: RegType::RT_NONE,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x08] = Instruction {
         i_type: InType::IN_LD,
         mode: AddrMode::AM_A16_R,
@@ -199,7 +290,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
-
+    INSTRUCTIONS[0x09] = Instruction {
+        i_type: InType::IN_ADD,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_HL,
+        reg_2: RegType::RT_BC,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x0A] = Instruction {
         i_type: InType::IN_LD,
         mode: AddrMode::AM_R_MR,
@@ -208,7 +306,30 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
-
+    INSTRUCTIONS[0x0B] = Instruction {
+        i_type: InType::IN_DEC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_BC,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x0C] = Instruction {
+        i_type: InType::IN_INC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_C,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x0D] = Instruction {
+        i_type: InType::IN_DEC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_C,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x0E] = Instruction {
         i_type: InType::IN_LD,
         mode: AddrMode::AM_R_D8,
@@ -217,8 +338,24 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0x0F] = Instruction {
+        i_type: InType::IN_RRCA,
+        mode: AddrMode::AM_IMP,
+        reg_1: RegType::RT_NONE,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
 
     // 0x1X
+    INSTRUCTIONS[0x10] = Instruction {
+        i_type: InType::IN_STOP,
+        mode: AddrMode::AM_IMP,
+        reg_1: RegType::RT_NONE,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x11] = Instruction {
         i_type: InType::IN_LD,
         mode: AddrMode::AM_R_D16,
@@ -235,6 +372,22 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0x13] = Instruction {
+        i_type: InType::IN_INC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_DE,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x14] = Instruction {
+        i_type: InType::IN_INC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_D,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x15] = Instruction {
         i_type: InType::IN_DEC,
         mode: AddrMode::AM_R,
@@ -251,6 +404,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0x17] = Instruction {
+        i_type: InType::IN_RLA,
+        mode: AddrMode::AM_IMP,
+        reg_1: RegType::RT_NONE,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x18] = Instruction {
         i_type: InType::IN_JR,
         mode: AddrMode::AM_D8,
@@ -259,6 +420,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0x19] = Instruction {
+        i_type: InType::IN_ADD,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_HL,
+        reg_2: RegType::RT_DE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x1A] = Instruction {
         i_type: InType::IN_LD,
         mode: AddrMode::AM_R_MR,
@@ -267,6 +436,30 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0x1B] = Instruction {
+        i_type: InType::IN_DEC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_DE,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x1C] = Instruction {
+        i_type: InType::IN_INC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_E,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x1D] = Instruction {
+        i_type: InType::IN_DEC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_E,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x1E] = Instruction {
         i_type: InType::IN_LD,
         mode: AddrMode::AM_R_D8,
@@ -275,6 +468,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0x1F] = Instruction {
+        i_type: InType::IN_RRA,
+        mode: AddrMode::AM_IMP,
+        reg_1: RegType::RT_NONE,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
 
     //0x2X
     INSTRUCTIONS[0x20] = Instruction {
@@ -301,6 +502,22 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0x23] = Instruction {
+        i_type: InType::IN_INC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_HL,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x24] = Instruction {
+        i_type: InType::IN_INC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_H,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x25] = Instruction {
         i_type: InType::IN_DEC,
         mode: AddrMode::AM_R,
@@ -317,6 +534,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0x27] = Instruction {
+        i_type: InType::IN_DAA,
+        mode: AddrMode::AM_IMP,
+        reg_1: RegType::RT_NONE,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x28] = Instruction {
         i_type: InType::IN_JR,
         mode: AddrMode::AM_D8,
@@ -325,6 +550,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_Z,
         param: 0,
     };
+    INSTRUCTIONS[0x29] = Instruction {
+        i_type: InType::IN_ADD,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_HL,
+        reg_2: RegType::RT_HL,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x2A] = Instruction {
         i_type: InType::IN_LD,
         mode: AddrMode::AM_R_HLI,
@@ -333,6 +566,30 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0x2B] = Instruction {
+        i_type: InType::IN_DEC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_HL,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x2C] = Instruction {
+        i_type: InType::IN_INC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_L,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x2D] = Instruction {
+        i_type: InType::IN_DEC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_L,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x2E] = Instruction {
         i_type: InType::IN_LD,
         mode: AddrMode::AM_R_D8,
@@ -341,6 +598,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0x2F] = Instruction {
+        i_type: InType::IN_CPL,
+        mode: AddrMode::AM_IMP,
+        reg_1: RegType::RT_NONE,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
 
     //0x3X
     INSTRUCTIONS[0x30] = Instruction {
@@ -367,9 +632,25 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0x33] = Instruction {
+        i_type: InType::IN_INC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_SP,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x34] = Instruction {
+        i_type: InType::IN_INC,
+        mode: AddrMode::AM_MR,
+        reg_1: RegType::RT_HL,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x35] = Instruction {
         i_type: InType::IN_DEC,
-        mode: AddrMode::AM_R,
+        mode: AddrMode::AM_MR,
         reg_1: RegType::RT_HL,
         reg_2: RegType::RT_NONE,
         cond: CondType::CT_NONE,
@@ -383,6 +664,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0x37] = Instruction {
+        i_type: InType::IN_SCF,
+        mode: AddrMode::AM_IMP,
+        reg_1: RegType::RT_NONE,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x38] = Instruction {
         i_type: InType::IN_JR,
         mode: AddrMode::AM_D8,
@@ -391,6 +680,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_C,
         param: 0,
     };
+    INSTRUCTIONS[0x39] = Instruction {
+        i_type: InType::IN_ADD,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_HL,
+        reg_2: RegType::RT_SP,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x3A] = Instruction {
         i_type: InType::IN_LD,
         mode: AddrMode::AM_R_HLD,
@@ -399,6 +696,30 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0x3B] = Instruction {
+        i_type: InType::IN_DEC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_SP,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x3C] = Instruction {
+        i_type: InType::IN_INC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x3D] = Instruction {
+        i_type: InType::IN_DEC,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0x3E] = Instruction {
         i_type: InType::IN_LD,
         mode: AddrMode::AM_R_D8,
@@ -407,6 +728,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0x3F] = Instruction {
+        i_type: InType::IN_CCF,
+        mode: AddrMode::AM_IMP,
+        reg_1: RegType::RT_NONE,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
 
     //0x4X
     INSTRUCTIONS[0x40] = Instruction {
@@ -928,33 +1257,544 @@ pub unsafe fn set_instructions() {
         param: 0,
     };
 
-    INSTRUCTIONS[0xAF] = Instruction {
-        i_type: InType::IN_XOR,
-        mode: AddrMode::AM_R,
+    //0x8X
+    INSTRUCTIONS[0x80] = Instruction {
+        i_type: InType::IN_ADD,
+        mode: AddrMode::AM_R_R,
         reg_1: RegType::RT_A,
-        reg_2: RegType::RT_NONE,
+        reg_2: RegType::RT_B,
         cond: CondType::CT_NONE,
         param: 0,
     };
-
-    // 0xCX
-    INSTRUCTIONS[0xC0] = Instruction {
-        i_type: InType::IN_RET,
-        mode: AddrMode::AM_IMP,
-        reg_1: RegType::RT_NONE,
-        reg_2: RegType::RT_NONE,
-        cond: CondType::CT_NZ,
+    INSTRUCTIONS[0x81] = Instruction {
+        i_type: InType::IN_ADD,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_C,
+        cond: CondType::CT_NONE,
         param: 0,
     };
-    INSTRUCTIONS[0xC1] = Instruction {
-        i_type: InType::IN_POP,
-        mode: AddrMode::AM_R,
-        reg_1: RegType::RT_BC,
-        reg_2: RegType::RT_NONE,
+    INSTRUCTIONS[0x82] = Instruction {
+        i_type: InType::IN_ADD,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_D,
         cond: CondType::CT_NONE,
         param: 0,
     };
-    INSTRUCTIONS[0xC2] = Instruction {
+    INSTRUCTIONS[0x83] = Instruction {
+        i_type: InType::IN_ADD,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_E,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x84] = Instruction {
+        i_type: InType::IN_ADD,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_H,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x85] = Instruction {
+        i_type: InType::IN_ADD,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_L,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x86] = Instruction {
+        i_type: InType::IN_ADD,
+        mode: AddrMode::AM_R_MR,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_HL,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x87] = Instruction {
+        i_type: InType::IN_ADD,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_A,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x88] = Instruction {
+        i_type: InType::IN_ADC,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_B,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x89] = Instruction {
+        i_type: InType::IN_ADC,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_C,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x8A] = Instruction {
+        i_type: InType::IN_ADC,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_D,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x8B] = Instruction {
+        i_type: InType::IN_ADC,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_E,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x8C] = Instruction {
+        i_type: InType::IN_ADC,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_H,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x8D] = Instruction {
+        i_type: InType::IN_ADC,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_L,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x8E] = Instruction {
+        i_type: InType::IN_ADC,
+        mode: AddrMode::AM_R_MR,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_HL,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x8F] = Instruction {
+        i_type: InType::IN_ADC,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_A,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+
+    //0x9X
+    INSTRUCTIONS[0x90] = Instruction {
+        i_type: InType::IN_SUB,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_B,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x91] = Instruction {
+        i_type: InType::IN_SUB,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_C,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x92] = Instruction {
+        i_type: InType::IN_SUB,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_D,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x93] = Instruction {
+        i_type: InType::IN_SUB,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_E,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x94] = Instruction {
+        i_type: InType::IN_SUB,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_H,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x95] = Instruction {
+        i_type: InType::IN_SUB,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_L,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x96] = Instruction {
+        i_type: InType::IN_SUB,
+        mode: AddrMode::AM_R_MR,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_HL,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x97] = Instruction {
+        i_type: InType::IN_SUB,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_A,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x98] = Instruction {
+        i_type: InType::IN_SBC,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_B,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x99] = Instruction {
+        i_type: InType::IN_SBC,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_C,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x9A] = Instruction {
+        i_type: InType::IN_SBC,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_D,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x9B] = Instruction {
+        i_type: InType::IN_SBC,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_E,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x9C] = Instruction {
+        i_type: InType::IN_SBC,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_H,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x9D] = Instruction {
+        i_type: InType::IN_SBC,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_L,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x9E] = Instruction {
+        i_type: InType::IN_SBC,
+        mode: AddrMode::AM_R_MR,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_HL,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0x9F] = Instruction {
+        i_type: InType::IN_SBC,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_A,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+
+    //0xAX
+    INSTRUCTIONS[0xA0] = Instruction {
+        i_type: InType::IN_AND,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_B,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xA1] = Instruction {
+        i_type: InType::IN_AND,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_C,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xA2] = Instruction {
+        i_type: InType::IN_AND,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_D,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xA3] = Instruction {
+        i_type: InType::IN_AND,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_E,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xA4] = Instruction {
+        i_type: InType::IN_AND,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_H,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xA5] = Instruction {
+        i_type: InType::IN_AND,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_L,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xA6] = Instruction {
+        i_type: InType::IN_AND,
+        mode: AddrMode::AM_R_MR,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_HL,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xA7] = Instruction {
+        i_type: InType::IN_AND,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_A,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xA8] = Instruction {
+        i_type: InType::IN_XOR,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_B,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xA9] = Instruction {
+        i_type: InType::IN_XOR,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_C,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xAA] = Instruction {
+        i_type: InType::IN_XOR,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_D,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xAB] = Instruction {
+        i_type: InType::IN_XOR,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_E,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xAC] = Instruction {
+        i_type: InType::IN_XOR,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_H,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xAD] = Instruction {
+        i_type: InType::IN_XOR,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_L,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xAE] = Instruction {
+        i_type: InType::IN_XOR,
+        mode: AddrMode::AM_R_MR,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_HL,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xAF] = Instruction {
+        i_type: InType::IN_XOR,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_A,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+
+    //0xBX
+    INSTRUCTIONS[0xB0] = Instruction {
+        i_type: InType::IN_OR,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_B,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xB1] = Instruction {
+        i_type: InType::IN_OR,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_C,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xB2] = Instruction {
+        i_type: InType::IN_OR,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_D,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xB3] = Instruction {
+        i_type: InType::IN_OR,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_E,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xB4] = Instruction {
+        i_type: InType::IN_OR,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_H,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xB5] = Instruction {
+        i_type: InType::IN_OR,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_L,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xB6] = Instruction {
+        i_type: InType::IN_OR,
+        mode: AddrMode::AM_R_MR,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_HL,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xB7] = Instruction {
+        i_type: InType::IN_OR,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_A,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xB8] = Instruction {
+        i_type: InType::IN_CP,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_B,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xB9] = Instruction {
+        i_type: InType::IN_CP,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_C,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xBA] = Instruction {
+        i_type: InType::IN_CP,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_D,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xBB] = Instruction {
+        i_type: InType::IN_CP,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_E,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xBC] = Instruction {
+        i_type: InType::IN_CP,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_H,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xBD] = Instruction {
+        i_type: InType::IN_CP,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_L,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xBE] = Instruction {
+        i_type: InType::IN_CP,
+        mode: AddrMode::AM_R_MR,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_HL,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xBF] = Instruction {
+        i_type: InType::IN_CP,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_A,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+
+    // 0xCX
+    INSTRUCTIONS[0xC0] = Instruction {
+        i_type: InType::IN_RET,
+        mode: AddrMode::AM_IMP,
+        reg_1: RegType::RT_NONE,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NZ,
+        param: 0,
+    };
+    INSTRUCTIONS[0xC1] = Instruction {
+        i_type: InType::IN_POP,
+        mode: AddrMode::AM_R,
+        reg_1: RegType::RT_BC,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xC2] = Instruction {
         i_type: InType::IN_JP,
         mode: AddrMode::AM_D16,
         reg_1: RegType::RT_NONE,
@@ -986,6 +1826,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0xC6] = Instruction {
+        i_type: InType::IN_ADD,
+        mode: AddrMode::AM_R_D8,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0xC7] = Instruction {
         i_type: InType::IN_RST,
         mode: AddrMode::AM_IMP,
@@ -1018,6 +1866,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_Z,
         param: 0,
     };
+    INSTRUCTIONS[0xCB] = Instruction {
+        i_type: InType::IN_CB,
+        mode: AddrMode::AM_D8,
+        reg_1: RegType::RT_NONE,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0xCC] = Instruction {
         i_type: InType::IN_CALL,
         mode: AddrMode::AM_D16,
@@ -1034,6 +1890,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0xCE] = Instruction {
+        i_type: InType::IN_ADC,
+        mode: AddrMode::AM_R_D8,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0xCF] = Instruction {
         i_type: InType::IN_RST,
         mode: AddrMode::AM_IMP,
@@ -1084,6 +1948,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0xD6] = Instruction {
+        i_type: InType::IN_SUB,
+        mode: AddrMode::AM_R_D8,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0xD7] = Instruction {
         i_type: InType::IN_RST,
         mode: AddrMode::AM_IMP,
@@ -1124,6 +1996,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_C,
         param: 0,
     };
+    INSTRUCTIONS[0xDE] = Instruction {
+        i_type: InType::IN_SBC,
+        mode: AddrMode::AM_R_D8,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0xDF] = Instruction {
         i_type: InType::IN_RST,
         mode: AddrMode::AM_IMP,
@@ -1152,7 +2032,7 @@ pub unsafe fn set_instructions() {
     };
     INSTRUCTIONS[0xE2] = Instruction {
         i_type: InType::IN_LD,
-        mode: AddrMode::AM_R_MR,
+        mode: AddrMode::AM_MR_R,
         reg_1: RegType::RT_C,
         reg_2: RegType::RT_A,
         cond: CondType::CT_NONE,
@@ -1166,6 +2046,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0xE6] = Instruction {
+        i_type: InType::IN_AND,
+        mode: AddrMode::AM_R_D8,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0xE7] = Instruction {
         i_type: InType::IN_RST,
         mode: AddrMode::AM_IMP,
@@ -1174,9 +2062,17 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0x20,
     };
+    INSTRUCTIONS[0xE8] = Instruction {
+        i_type: InType::IN_ADD,
+        mode: AddrMode::AM_R_D8,
+        reg_1: RegType::RT_SP,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0xE9] = Instruction {
-        i_type: InType::IN_JP,
-        mode: AddrMode::AM_MR,
+        i_type: InType::IN_JPHL,
+        mode: AddrMode::AM_R,
         reg_1: RegType::RT_HL,
         reg_2: RegType::RT_NONE,
         cond: CondType::CT_NONE,
@@ -1190,6 +2086,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0xEE] = Instruction {
+        i_type: InType::IN_XOR,
+        mode: AddrMode::AM_R_D8,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0xEF] = Instruction {
         i_type: InType::IN_RST,
         mode: AddrMode::AM_IMP,
@@ -1240,6 +2144,14 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0xF6] = Instruction {
+        i_type: InType::IN_OR,
+        mode: AddrMode::AM_R_D8,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0xF7] = Instruction {
         i_type: InType::IN_RST,
         mode: AddrMode::AM_IMP,
@@ -1248,6 +2160,22 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0x30,
     };
+    INSTRUCTIONS[0xF8] = Instruction {
+        i_type: InType::IN_LD,
+        mode: AddrMode::AM_HL_SPR,
+        reg_1: RegType::RT_HL,
+        reg_2: RegType::RT_SP,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xF9] = Instruction {
+        i_type: InType::IN_LD,
+        mode: AddrMode::AM_R_R,
+        reg_1: RegType::RT_SP,
+        reg_2: RegType::RT_HL,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0xFA] = Instruction {
         i_type: InType::IN_LD,
         mode: AddrMode::AM_R_A16,
@@ -1256,6 +2184,22 @@ pub unsafe fn set_instructions() {
         cond: CondType::CT_NONE,
         param: 0,
     };
+    INSTRUCTIONS[0xFB] = Instruction {
+        i_type: InType::IN_EI,
+        mode: AddrMode::AM_IMP,
+        reg_1: RegType::RT_NONE,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
+    INSTRUCTIONS[0xFE] = Instruction {
+        i_type: InType::IN_CP,
+        mode: AddrMode::AM_R_D8,
+        reg_1: RegType::RT_A,
+        reg_2: RegType::RT_NONE,
+        cond: CondType::CT_NONE,
+        param: 0,
+    };
     INSTRUCTIONS[0xFF] = Instruction {
         i_type: InType::IN_RST,
         mode: AddrMode::AM_IMP,
@@ -1274,3 +2218,7 @@ pub unsafe fn instruction_by_opcode(code: u8) -> Option<Instruction> {
 
     Some(INSTRUCTIONS[code])
 }
+
+pub unsafe fn cb_instruction_by_opcode(code: u8) -> Instruction {
+    CB_INSTRUCTIONS[code as usize]
+}
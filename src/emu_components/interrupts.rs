@@ -0,0 +1,72 @@
+use super::cpu::{cpu_get_context, CpuContext};
+use super::emu::emu_cycles;
+use super::stack::stack_push;
+
+// Bit positions in IE (0xFFFF) and IF (0xFF0F), lowest bit has the highest priority
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptType {
+    IT_VBLANK = 1,
+    IT_LCD_STAT = 2,
+    IT_TIMER = 4,
+    IT_SERIAL = 8,
+    IT_JOYPAD = 16,
+}
+
+static INTERRUPTS: [(InterruptType, u16); 5] = [
+    (InterruptType::IT_VBLANK, 0x40),
+    (InterruptType::IT_LCD_STAT, 0x48),
+    (InterruptType::IT_TIMER, 0x50),
+    (InterruptType::IT_SERIAL, 0x58),
+    (InterruptType::IT_JOYPAD, 0x60),
+];
+
+pub fn interrupt_pending(ctx: &CpuContext) -> u8 {
+    ctx.int_flags & ctx.ie_register & 0x1F
+}
+
+/*
+    Interrupt dispatch takes 5 M-cycles:
+
+    M1-M2: internal delay (PC is decremented back on real hardware)
+    M3:    push PC high byte
+    M4:    push PC low byte, the interrupt to service is chosen here
+    M5:    jump to the vector
+
+    The high byte push can overwrite IE (SP=0x0000), in which case the
+    interrupt is cancelled and PC ends up at 0x0000.
+*/
+pub unsafe fn cpu_handle_interrupts(ctx: &mut CpuContext) -> bool {
+    if interrupt_pending(ctx) == 0 {
+        return false;
+    }
+
+    ctx.int_master_enabled = false;
+    ctx.halted = false;
+
+    emu_cycles(2);
+
+    let pc = ctx.regs.pc;
+    stack_push((pc >> 8) as u8);
+    emu_cycles(1);
+
+    let pending = interrupt_pending(ctx);
+    stack_push(pc as u8);
+    emu_cycles(1);
+
+    ctx.regs.pc = match INTERRUPTS.iter().find(|(it, _)| pending & (*it as u8) != 0) {
+        Some((it, address)) => {
+            ctx.int_flags &= !(*it as u8);
+            *address
+        }
+        None => 0x0000,
+    };
+
+    emu_cycles(1);
+    true
+}
+
+pub unsafe fn cpu_request_interrupt(it: InterruptType) {
+    let ctx = cpu_get_context();
+    ctx.int_flags |= it as u8;
+}
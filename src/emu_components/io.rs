@@ -0,0 +1,60 @@
+use super::apu::{apu_read, apu_write};
+use super::boot::{boot_key0_write, boot_write};
+use super::cpu::{cpu_get_int_flags, cpu_set_int_flags};
+use super::emu::{emu_cgb_mode, emu_key1_read, emu_key1_write};
+use super::joypad::{joypad_read, joypad_write};
+use super::lcd::{lcd_read, lcd_write};
+use super::ppu::{ppu_vram_bank_read, ppu_vram_bank_write};
+use super::ram::{wram_bank_read, wram_bank_write};
+use super::serial::{serial_read, serial_write};
+use super::timer::{timer_read, timer_write};
+
+// 0xFF00          : Joypad
+// 0xFF01 - 0xFF02 : Serial
+// 0xFF04 - 0xFF07 : Timer
+// 0xFF0F          : Interrupt Flags
+// 0xFF10 - 0xFF26 : Audio
+// 0xFF30 - 0xFF3F : Wave RAM
+// 0xFF40 - 0xFF4B : LCD
+// 0xFF4C          : KEY0, CGB mode select - only while the CGB boot ROM is mapped
+// 0xFF4D          : Speed Switch - Color only
+// 0xFF4F          : VRAM Bank - Color only
+// 0xFF50          : Boot ROM unmap, write only
+// 0xFF68 - 0xFF6B : Palettes - Color only
+// 0xFF70          : WRAM Bank - Color only
+//
+// Anything else is unmapped, reads 0xFF and ignores writes.
+
+pub unsafe fn io_read(address: u16) -> u8 {
+    match address {
+        0xFF00 => joypad_read(),
+        0xFF01..=0xFF02 => serial_read(address),
+        0xFF04..=0xFF07 => timer_read(address),
+        0xFF0F => cpu_get_int_flags() | 0xE0,
+        0xFF10..=0xFF26 | 0xFF30..=0xFF3F => apu_read(address),
+        0xFF40..=0xFF4B => lcd_read(address),
+        0xFF4D if emu_cgb_mode() => emu_key1_read(),
+        0xFF4F if emu_cgb_mode() => ppu_vram_bank_read(),
+        0xFF68..=0xFF6B if emu_cgb_mode() => lcd_read(address),
+        0xFF70 if emu_cgb_mode() => wram_bank_read(),
+        _ => 0xFF,
+    }
+}
+
+pub unsafe fn io_write(address: u16, value: u8) {
+    match address {
+        0xFF00 => joypad_write(value),
+        0xFF01..=0xFF02 => serial_write(address, value),
+        0xFF04..=0xFF07 => timer_write(address, value),
+        0xFF0F => cpu_set_int_flags(value),
+        0xFF10..=0xFF26 | 0xFF30..=0xFF3F => apu_write(address, value),
+        0xFF40..=0xFF4B => lcd_write(address, value),
+        0xFF4C => boot_key0_write(value),
+        0xFF4D if emu_cgb_mode() => emu_key1_write(value),
+        0xFF4F if emu_cgb_mode() => ppu_vram_bank_write(value),
+        0xFF50 => boot_write(value),
+        0xFF68..=0xFF6B if emu_cgb_mode() => lcd_write(address, value),
+        0xFF70 if emu_cgb_mode() => wram_bank_write(value),
+        _ => (),
+    }
+}
@@ -0,0 +1,98 @@
+use super::common::bit;
+
+/*
+  0xFF00 P1/JOYP:
+
+  bit 5: select action buttons (0 = selected)
+  bit 4: select direction buttons (0 = selected)
+  bit 3: Start / Down    (0 = pressed)
+  bit 2: Select / Up
+  bit 1: B / Left
+  bit 0: A / Right
+*/
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JoypadState {
+    pub start: bool,
+    pub select: bool,
+    pub a: bool,
+    pub b: bool,
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+}
+
+#[derive(Debug)]
+pub struct JoypadContext {
+    button_sel: bool,
+    dir_sel: bool,
+    // Private until an input source writes it, nothing is ever pressed for now
+    state: JoypadState,
+}
+
+static mut CTX: JoypadContext = JoypadContext {
+    button_sel: false,
+    dir_sel: false,
+    state: JoypadState {
+        start: false,
+        select: false,
+        a: false,
+        b: false,
+        up: false,
+        down: false,
+        left: false,
+        right: false,
+    },
+};
+
+// Both button groups start selected, P1 reads 0xCF with nothing pressed
+pub unsafe fn joypad_init() {
+    CTX.button_sel = true;
+    CTX.dir_sel = true;
+    CTX.state = JoypadState::default();
+}
+
+pub unsafe fn joypad_write(value: u8) {
+    CTX.button_sel = !bit(value, 5);
+    CTX.dir_sel = !bit(value, 4);
+}
+
+unsafe fn joypad_lines() -> u8 {
+    let mut output = 0x0F;
+    let state = &CTX.state;
+
+    if CTX.button_sel {
+        let pressed = [state.a, state.b, state.select, state.start];
+        for (n, on) in pressed.iter().enumerate() {
+            if *on {
+                output &= !(1 << n);
+            }
+        }
+    }
+
+    if CTX.dir_sel {
+        let pressed = [state.right, state.left, state.up, state.down];
+        for (n, on) in pressed.iter().enumerate() {
+            if *on {
+                output &= !(1 << n);
+            }
+        }
+    }
+
+    output
+}
+
+pub unsafe fn joypad_read() -> u8 {
+    let mut output = 0xC0 | joypad_lines();
+
+    if !CTX.button_sel {
+        output |= 0x20;
+    }
+
+    if !CTX.dir_sel {
+        output |= 0x10;
+    }
+
+    output
+}
@@ -0,0 +1,276 @@
+use super::boot::boot_rom_mapped;
+use super::common::bit;
+use super::dma::dma_start;
+use super::emu::{emu_cgb_mode, emu_model};
+use super::model::HardwareModel;
+
+/*
+  LCD registers:
+
+  0xFF40 LCDC : LCD control
+  0xFF41 STAT : bits 3-6 interrupt sources, bit 2 LY=LYC (read only), bits 0-1 mode (read only)
+  0xFF42 SCY
+  0xFF43 SCX
+  0xFF44 LY   : read only
+  0xFF45 LYC
+  0xFF46 DMA
+  0xFF47 BGP
+  0xFF48 OBP0
+  0xFF49 OBP1
+  0xFF4A WY
+  0xFF4B WX
+
+  LCDC bits:
+
+  7 : LCD enable
+  6 : window tile map, 0x9800 / 0x9C00
+  5 : window enable
+  4 : BG/window tile data, 0x8800 (signed index from 0x9000) / 0x8000
+  3 : BG tile map, 0x9800 / 0x9C00
+  2 : OBJ size, 8x8 / 8x16
+  1 : OBJ enable
+  0 : BG/window enable on DMG, BG/window priority over OBJ in CGB mode
+
+  CGB palettes:
+
+  0xFF68 BCPS/BGPI : bit 7 auto increment, bits 0-5 index
+  0xFF69 BCPD/BGPD
+  0xFF6A OCPS/OBPI
+  0xFF6B OCPD/OBPD
+*/
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LcdMode {
+    MODE_HBLANK,
+    MODE_VBLANK,
+    MODE_OAM,
+    MODE_XFER,
+}
+
+#[derive(Debug)]
+pub struct LcdContext {
+    pub lcdc: u8,
+    pub stat: u8,
+    pub scy: u8,
+    pub scx: u8,
+    pub ly: u8,
+    pub lyc: u8,
+    pub dma: u8,
+    pub bgp: u8,
+    pub obp0: u8,
+    pub obp1: u8,
+    pub wy: u8,
+    pub wx: u8,
+
+    pub bcps: u8,
+    pub ocps: u8,
+    pub bg_palette_ram: [u8; 64],
+    pub obj_palette_ram: [u8; 64],
+}
+
+static mut CTX: LcdContext = LcdContext {
+    lcdc: 0x91,
+    stat: 0,
+    scy: 0,
+    scx: 0,
+    ly: 0,
+    lyc: 0,
+    dma: 0xFF,
+    bgp: 0xFC,
+    obp0: 0xFF,
+    obp1: 0xFF,
+    wy: 0,
+    wx: 0,
+    bcps: 0,
+    ocps: 0,
+    bg_palette_ram: [0xFF; 64],
+    obj_palette_ram: [0xFF; 64],
+};
+
+// Colors the CGB boot ROM gives DMG carts missing from its title table, as RGB555
+const COMPAT_BG_PALETTE: [u16; 4] = [0x7FFF, 0x1BEF, 0x6180, 0x0000];
+const COMPAT_OBJ_PALETTE: [u16; 4] = [0x7FFF, 0x421F, 0x1CF2, 0x0000];
+
+pub unsafe fn lcd_get_context() -> &'static mut LcdContext {
+    &mut CTX
+}
+
+/*
+  At power-on the LCD is off with blank palettes. After the boot ROM:
+
+  LCDC 0x91, STAT 0x85 (0x81 on DMG0), BGP 0xFC, DMA 0xFF (0x00 on CGB/AGB),
+  everything else 0x00 and OBP0/OBP1 left uninitialised. The PPU position
+  itself is set up by ppu_init.
+*/
+pub unsafe fn lcd_init() {
+    let model = emu_model();
+
+    CTX.scy = 0;
+    CTX.scx = 0;
+    CTX.lyc = 0;
+    CTX.wy = 0;
+    CTX.wx = 0;
+    CTX.obp0 = 0xFF;
+    CTX.obp1 = 0xFF;
+
+    if boot_rom_mapped() {
+        CTX.lcdc = 0;
+        CTX.stat = 0;
+        CTX.bgp = 0;
+        CTX.dma = 0;
+        return;
+    }
+
+    CTX.lcdc = 0x91;
+    CTX.stat = if model == HardwareModel::DMG0 {
+        0x01
+    } else {
+        0x05
+    };
+    CTX.bgp = 0xFC;
+    CTX.dma = if model.is_cgb() { 0x00 } else { 0xFF };
+
+    if model.is_cgb() && !emu_cgb_mode() {
+        lcd_compat_palettes();
+    }
+}
+
+/*
+  Without a boot ROM a DMG cart on CGB hardware gets the default
+  compatibility palette in BG palette 0 and OBJ palettes 0 and 1. The
+  palettes picked from the title checksum and the boot ROM's button
+  combinations are only seen when booting a real CGB boot ROM.
+*/
+unsafe fn lcd_compat_palettes() {
+    for (i, color) in COMPAT_BG_PALETTE.iter().enumerate() {
+        CTX.bg_palette_ram[i * 2..i * 2 + 2].copy_from_slice(&color.to_le_bytes());
+    }
+
+    for palette in 0..2 {
+        for (i, color) in COMPAT_OBJ_PALETTE.iter().enumerate() {
+            let index = (palette * 4 + i) * 2;
+            CTX.obj_palette_ram[index..index + 2].copy_from_slice(&color.to_le_bytes());
+        }
+    }
+}
+
+pub unsafe fn lcdc_bgw_enable() -> bool {
+    bit(CTX.lcdc, 0)
+}
+
+pub unsafe fn lcdc_obj_enable() -> bool {
+    bit(CTX.lcdc, 1)
+}
+
+pub unsafe fn lcdc_obj_height() -> u8 {
+    if bit(CTX.lcdc, 2) {
+        16
+    } else {
+        8
+    }
+}
+
+pub unsafe fn lcdc_bg_map_area() -> u16 {
+    if bit(CTX.lcdc, 3) {
+        0x9C00
+    } else {
+        0x9800
+    }
+}
+
+// Address of a BG/window tile, 0x8800 addressing takes the index as signed around 0x9000
+pub unsafe fn lcdc_bgw_tile_address(tile: u8) -> u16 {
+    if bit(CTX.lcdc, 4) {
+        0x8000 + tile as u16 * 16
+    } else {
+        (0x9000 + (tile as i8) as i32 * 16) as u16
+    }
+}
+
+pub unsafe fn lcdc_win_enable() -> bool {
+    bit(CTX.lcdc, 5)
+}
+
+pub unsafe fn lcdc_win_map_area() -> u16 {
+    if bit(CTX.lcdc, 6) {
+        0x9C00
+    } else {
+        0x9800
+    }
+}
+
+// STAT mode bits, the PPU reports mode 0 while the LCD is off
+pub unsafe fn lcd_get_mode() -> LcdMode {
+    if !bit(CTX.lcdc, 7) {
+        return LcdMode::MODE_HBLANK;
+    }
+
+    match CTX.stat & 0b11 {
+        0 => LcdMode::MODE_HBLANK,
+        1 => LcdMode::MODE_VBLANK,
+        2 => LcdMode::MODE_OAM,
+        _ => LcdMode::MODE_XFER,
+    }
+}
+
+pub unsafe fn lcd_set_mode(mode: LcdMode) {
+    CTX.stat = (CTX.stat & !0b11) | mode as u8;
+}
+
+pub unsafe fn lcd_read(address: u16) -> u8 {
+    match address {
+        0xFF40 => CTX.lcdc,
+        0xFF41 => CTX.stat | 0x80,
+        0xFF42 => CTX.scy,
+        0xFF43 => CTX.scx,
+        0xFF44 => CTX.ly,
+        0xFF45 => CTX.lyc,
+        0xFF46 => CTX.dma,
+        0xFF47 => CTX.bgp,
+        0xFF48 => CTX.obp0,
+        0xFF49 => CTX.obp1,
+        0xFF4A => CTX.wy,
+        0xFF4B => CTX.wx,
+        0xFF68 => CTX.bcps | 0x40,
+        0xFF69 => CTX.bg_palette_ram[(CTX.bcps & 0x3F) as usize],
+        0xFF6A => CTX.ocps | 0x40,
+        0xFF6B => CTX.obj_palette_ram[(CTX.ocps & 0x3F) as usize],
+        _ => 0xFF,
+    }
+}
+
+pub unsafe fn lcd_write(address: u16, value: u8) {
+    match address {
+        0xFF40 => CTX.lcdc = value,
+        0xFF41 => CTX.stat = (CTX.stat & 0x07) | (value & 0x78),
+        0xFF42 => CTX.scy = value,
+        0xFF43 => CTX.scx = value,
+        0xFF44 => (),
+        0xFF45 => CTX.lyc = value,
+        0xFF46 => {
+            CTX.dma = value;
+            dma_start(value);
+        }
+        0xFF47 => CTX.bgp = value,
+        0xFF48 => CTX.obp0 = value,
+        0xFF49 => CTX.obp1 = value,
+        0xFF4A => CTX.wy = value,
+        0xFF4B => CTX.wx = value,
+        0xFF68 => CTX.bcps = value & 0xBF,
+        0xFF69 => {
+            CTX.bg_palette_ram[(CTX.bcps & 0x3F) as usize] = value;
+            if bit(CTX.bcps, 7) {
+                CTX.bcps = 0x80 | ((CTX.bcps + 1) & 0x3F);
+            }
+        }
+        0xFF6A => CTX.ocps = value & 0xBF,
+        0xFF6B => {
+            CTX.obj_palette_ram[(CTX.ocps & 0x3F) as usize] = value;
+            if bit(CTX.ocps, 7) {
+                CTX.ocps = 0x80 | ((CTX.ocps + 1) & 0x3F);
+            }
+        }
+        _ => (),
+    }
+}
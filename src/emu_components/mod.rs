@@ -1,13 +1,29 @@
+pub mod apu;
+pub mod archive;
+pub mod boot;
 pub mod bus;
 pub mod cart;
+pub mod cart_header;
 pub mod common;
 pub mod cpu;
 pub mod cpu_fetch;
 pub mod cpu_proc;
 pub mod cpu_util;
+pub mod dma;
 pub mod emu;
+pub mod info;
 pub mod instructions;
+pub mod interrupts;
+pub mod io;
+pub mod joypad;
+pub mod lcd;
+pub mod model;
+pub mod patch;
 pub mod ppu;
+pub mod ppu_pipeline;
+pub mod ppu_render;
+pub mod ppu_sm;
 pub mod ram;
+pub mod serial;
 pub mod stack;
 pub mod timer;
@@ -0,0 +1,57 @@
+/*
+  Hardware models, selected with --model=<name>:
+
+  DMG0 : early DMG with the first boot ROM revision
+  DMG  : Game Boy
+  MGB  : Game Boy Pocket
+  SGB  : Super Game Boy
+  SGB2 : Super Game Boy 2
+  CGB  : Game Boy Color
+  AGB  : Game Boy Advance, runs the CGB boot ROM
+
+  Without a boot ROM every component starts in the state the model's boot
+  ROM leaves behind when it jumps to 0x0100.
+*/
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HardwareModel {
+    DMG0,
+    DMG,
+    MGB,
+    SGB,
+    SGB2,
+    CGB,
+    AGB,
+}
+
+impl HardwareModel {
+    pub fn from_name(name: &str) -> Option<HardwareModel> {
+        match name.to_ascii_lowercase().as_str() {
+            "dmg0" => Some(HardwareModel::DMG0),
+            "dmg" => Some(HardwareModel::DMG),
+            "mgb" => Some(HardwareModel::MGB),
+            "sgb" => Some(HardwareModel::SGB),
+            "sgb2" => Some(HardwareModel::SGB2),
+            "cgb" => Some(HardwareModel::CGB),
+            "agb" => Some(HardwareModel::AGB),
+            _ => None,
+        }
+    }
+
+    pub fn is_cgb(self) -> bool {
+        matches!(self, HardwareModel::CGB | HardwareModel::AGB)
+    }
+
+    pub fn is_sgb(self) -> bool {
+        matches!(self, HardwareModel::SGB | HardwareModel::SGB2)
+    }
+
+    pub fn boot_rom_size(self) -> usize {
+        if self.is_cgb() {
+            0x900
+        } else {
+            0x100
+        }
+    }
+}
@@ -0,0 +1,646 @@
+use std::error::Error;
+use std::fmt;
+
+/*
+  Soft patches, applied to the ROM image in memory before the header is parsed.
+
+  IPS "PATCH" : records of 24-bit offset, 16-bit size and data, size 0 is
+                an RLE run of 16-bit count and value. "EOF" ends the patch,
+                optionally followed by a 24-bit size to truncate to.
+
+  UPS "UPS1"  : source and target sizes, then runs of skip length and bytes
+                XORed into the source until a 0x00.
+
+  BPS "BPS1"  : source size, target size and metadata, then actions
+                building the target from source reads, patch data and
+                copies out of the source or the target built so far.
+
+  UPS and BPS end with the CRC32s of the source, target and patch, all of
+  which are verified, after the source size in the header.
+*/
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchFormat {
+    IPS,
+    UPS,
+    BPS,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchError {
+    UnknownFormat,
+    Truncated(PatchFormat),
+    OutOfRange(PatchFormat),
+    BadPatchCrc { expected: u32, actual: u32 },
+    SourceSizeMismatch { expected: usize, actual: usize },
+    BadSourceCrc { expected: u32, actual: u32 },
+    BadTargetCrc { expected: u32, actual: u32 },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatchError::UnknownFormat => write!(f, "not an IPS, UPS or BPS patch"),
+            PatchError::Truncated(format) => write!(f, "{:?} patch is truncated", format),
+            PatchError::OutOfRange(format) => {
+                write!(f, "{:?} patch reads or writes outside the ROM", format)
+            }
+            PatchError::BadPatchCrc { expected, actual } => write!(
+                f,
+                "patch CRC32 is {:08x}, computed {:08x}",
+                expected, actual
+            ),
+            PatchError::SourceSizeMismatch { expected, actual } => write!(
+                f,
+                "patch expects a ROM of {} bytes, got {}",
+                expected, actual
+            ),
+            PatchError::BadSourceCrc { expected, actual } => write!(
+                f,
+                "patch expects a ROM with CRC32 {:08x}, got {:08x}",
+                expected, actual
+            ),
+            PatchError::BadTargetCrc { expected, actual } => write!(
+                f,
+                "patched ROM CRC32 is {:08x}, expected {:08x}",
+                actual, expected
+            ),
+        }
+    }
+}
+
+impl Error for PatchError {}
+
+// Nothing larger than the biggest mappers can address is ever produced
+const MAX_TARGET_SIZE: usize = 0x4000000;
+
+struct PatchReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    format: PatchFormat,
+}
+
+impl<'a> PatchReader<'a> {
+    fn byte(&mut self) -> Result<u8, PatchError> {
+        match self.data.get(self.pos) {
+            Some(value) => {
+                self.pos += 1;
+                Ok(*value)
+            }
+            None => Err(PatchError::Truncated(self.format)),
+        }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], PatchError> {
+        if self.data.len() - self.pos < len {
+            return Err(PatchError::Truncated(self.format));
+        }
+
+        self.pos += len;
+        Ok(&self.data[self.pos - len..self.pos])
+    }
+
+    // Big endian, IPS only
+    fn number(&mut self, len: usize) -> Result<usize, PatchError> {
+        let mut value = 0;
+        for b in self.bytes(len)? {
+            value = (value << 8) | *b as usize;
+        }
+
+        Ok(value)
+    }
+
+    // UPS and BPS numbers, 7 bits per byte with bit 7 marking the last one
+    fn varint(&mut self) -> Result<usize, PatchError> {
+        let mut value: usize = 0;
+        let mut shift: usize = 1;
+
+        loop {
+            let x = self.byte()?;
+            value = ((x & 0x7F) as usize)
+                .checked_mul(shift)
+                .and_then(|v| v.checked_add(value))
+                .ok_or(PatchError::OutOfRange(self.format))?;
+
+            if x & 0x80 != 0 {
+                return Ok(value);
+            }
+
+            shift = shift
+                .checked_shl(7)
+                .filter(|s| *s != 0)
+                .ok_or(PatchError::OutOfRange(self.format))?;
+            value = value
+                .checked_add(shift)
+                .ok_or(PatchError::OutOfRange(self.format))?;
+        }
+    }
+}
+
+fn crc32_le(data: &[u8]) -> u32 {
+    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+}
+
+// Checks the trailing patch CRC32 and returns the expected source and target CRC32s
+fn patch_footer(patch: &[u8], format: PatchFormat) -> Result<(u32, u32), PatchError> {
+    if patch.len() < 4 + 12 {
+        return Err(PatchError::Truncated(format));
+    }
+
+    let footer = &patch[patch.len() - 12..];
+    let expected = crc32_le(&footer[8..]);
+    let actual = crc32fast::hash(&patch[..patch.len() - 4]);
+
+    if expected != actual {
+        return Err(PatchError::BadPatchCrc { expected, actual });
+    }
+
+    Ok((crc32_le(&footer[0..]), crc32_le(&footer[4..])))
+}
+
+// The size is checked first, a wrong size means the wrong base ROM was picked
+fn check_source(source: &[u8], size: usize, expected: u32) -> Result<(), PatchError> {
+    if source.len() != size {
+        return Err(PatchError::SourceSizeMismatch {
+            expected: size,
+            actual: source.len(),
+        });
+    }
+
+    let actual = crc32fast::hash(source);
+
+    if actual != expected {
+        return Err(PatchError::BadSourceCrc { expected, actual });
+    }
+
+    Ok(())
+}
+
+fn check_target(target: &[u8], expected: u32) -> Result<(), PatchError> {
+    let actual = crc32fast::hash(target);
+
+    if actual != expected {
+        return Err(PatchError::BadTargetCrc { expected, actual });
+    }
+
+    Ok(())
+}
+
+fn ips_apply(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let mut target = rom.to_vec();
+    let mut reader = PatchReader {
+        data: patch,
+        pos: 5,
+        format: PatchFormat::IPS,
+    };
+
+    loop {
+        if reader.bytes(3)? == b"EOF" {
+            break;
+        }
+
+        reader.pos -= 3;
+        let offset = reader.number(3)?;
+        let size = reader.number(2)?;
+
+        let (len, run) = if size == 0 {
+            (reader.number(2)?, Some(reader.byte()?))
+        } else {
+            (size, None)
+        };
+
+        if target.len() < offset + len {
+            target.resize(offset + len, 0);
+        }
+
+        match run {
+            Some(value) => target[offset..offset + len].fill(value),
+            None => target[offset..offset + len].copy_from_slice(reader.bytes(len)?),
+        }
+    }
+
+    if patch.len() - reader.pos >= 3 {
+        target.truncate(reader.number(3)?);
+    }
+
+    Ok(target)
+}
+
+fn ups_apply(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let (source_crc, target_crc) = patch_footer(patch, PatchFormat::UPS)?;
+
+    let mut reader = PatchReader {
+        data: &patch[..patch.len() - 12],
+        pos: 4,
+        format: PatchFormat::UPS,
+    };
+
+    let source_size = reader.varint()?;
+    let target_size = reader.varint()?;
+
+    if target_size > MAX_TARGET_SIZE {
+        return Err(PatchError::OutOfRange(PatchFormat::UPS));
+    }
+
+    check_source(rom, source_size, source_crc)?;
+
+    let mut target = rom.to_vec();
+    target.resize(target_size, 0);
+
+    let mut pos: usize = 0;
+    while reader.pos < reader.data.len() {
+        pos = pos.saturating_add(reader.varint()?);
+
+        loop {
+            let x = reader.byte()?;
+
+            if x != 0 && pos < target.len() {
+                target[pos] ^= x;
+            }
+
+            pos = pos.saturating_add(1);
+            if x == 0 {
+                break;
+            }
+        }
+    }
+
+    check_target(&target, target_crc)?;
+    Ok(target)
+}
+
+// Signed BPS copy offsets keep the sign in bit 0
+fn bps_offset(reader: &mut PatchReader, current: usize) -> Result<usize, PatchError> {
+    let data = reader.varint()?;
+    let delta = data >> 1;
+
+    let offset = if data & 1 != 0 {
+        current.checked_sub(delta)
+    } else {
+        current.checked_add(delta)
+    };
+
+    offset.ok_or(PatchError::OutOfRange(PatchFormat::BPS))
+}
+
+fn bps_apply(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let (source_crc, target_crc) = patch_footer(patch, PatchFormat::BPS)?;
+
+    let mut reader = PatchReader {
+        data: &patch[..patch.len() - 12],
+        pos: 4,
+        format: PatchFormat::BPS,
+    };
+
+    let source_size = reader.varint()?;
+    let target_size = reader.varint()?;
+
+    if target_size > MAX_TARGET_SIZE {
+        return Err(PatchError::OutOfRange(PatchFormat::BPS));
+    }
+
+    check_source(rom, source_size, source_crc)?;
+
+    let metadata_size = reader.varint()?;
+    reader.bytes(metadata_size)?;
+
+    let out_of_range = PatchError::OutOfRange(PatchFormat::BPS);
+    let mut target: Vec<u8> = Vec::with_capacity(target_size);
+    let mut source_offset: usize = 0;
+    let mut target_offset: usize = 0;
+
+    while reader.pos < reader.data.len() {
+        let data = reader.varint()?;
+        let len = (data >> 2) + 1;
+
+        if len > target_size - target.len() {
+            return Err(out_of_range);
+        }
+
+        match data & 0b11 {
+            0 => {
+                //SourceRead, the same bytes as at this position in the source
+                let start = target.len();
+                let bytes = rom.get(start..start + len).ok_or(out_of_range)?;
+                target.extend_from_slice(bytes);
+            }
+            1 => {
+                //TargetRead
+                target.extend_from_slice(reader.bytes(len)?);
+            }
+            2 => {
+                //SourceCopy
+                source_offset = bps_offset(&mut reader, source_offset)?;
+                let end = source_offset.checked_add(len).ok_or(out_of_range)?;
+                target.extend_from_slice(rom.get(source_offset..end).ok_or(out_of_range)?);
+                source_offset = end;
+            }
+            _ => {
+                //TargetCopy, byte by byte as the copy may overlap its own output
+                target_offset = bps_offset(&mut reader, target_offset)?;
+
+                for _ in 0..len {
+                    let value = *target.get(target_offset).ok_or(out_of_range)?;
+                    target.push(value);
+                    target_offset += 1;
+                }
+            }
+        }
+    }
+
+    if target.len() != target_size {
+        return Err(PatchError::Truncated(PatchFormat::BPS));
+    }
+
+    check_target(&target, target_crc)?;
+    Ok(target)
+}
+
+pub fn patch_format(patch: &[u8]) -> Option<PatchFormat> {
+    if patch.starts_with(b"PATCH") {
+        Some(PatchFormat::IPS)
+    } else if patch.starts_with(b"UPS1") {
+        Some(PatchFormat::UPS)
+    } else if patch.starts_with(b"BPS1") {
+        Some(PatchFormat::BPS)
+    } else {
+        None
+    }
+}
+
+// Returns the patched ROM, the format is detected from the patch's magic bytes
+pub fn patch_apply(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    match patch_format(patch) {
+        Some(PatchFormat::IPS) => ips_apply(rom, patch),
+        Some(PatchFormat::UPS) => ups_apply(rom, patch),
+        Some(PatchFormat::BPS) => bps_apply(rom, patch),
+        None => Err(PatchError::UnknownFormat),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rom() -> Vec<u8> {
+        (0..64).collect()
+    }
+
+    fn varint(mut value: usize, out: &mut Vec<u8>) {
+        loop {
+            let x = (value & 0x7F) as u8;
+            value >>= 7;
+
+            if value == 0 {
+                out.push(0x80 | x);
+                return;
+            }
+
+            out.push(x);
+            value -= 1;
+        }
+    }
+
+    // Appends the source, target and patch CRC32s
+    fn footer(patch: &mut Vec<u8>, source: &[u8], target: &[u8]) {
+        patch.extend_from_slice(&crc32fast::hash(source).to_le_bytes());
+        patch.extend_from_slice(&crc32fast::hash(target).to_le_bytes());
+        let crc = crc32fast::hash(patch);
+        patch.extend_from_slice(&crc.to_le_bytes());
+    }
+
+    fn ups_patch(source: &[u8], target: &[u8]) -> Vec<u8> {
+        let mut patch = b"UPS1".to_vec();
+        varint(source.len(), &mut patch);
+        varint(target.len(), &mut patch);
+
+        // One hunk per run of changed bytes, each ending on a 0x00
+        let mut last = 0;
+        let mut i = 0;
+        while i < target.len() {
+            let xor = |i: usize| target[i] ^ source.get(i).copied().unwrap_or(0);
+
+            if xor(i) == 0 {
+                i += 1;
+                continue;
+            }
+
+            varint(i - last, &mut patch);
+            while i < target.len() && xor(i) != 0 {
+                patch.push(xor(i));
+                i += 1;
+            }
+            patch.push(0);
+            last = i + 1;
+        }
+
+        footer(&mut patch, source, target);
+        patch
+    }
+
+    // SourceRead of the first half, TargetRead of the rest
+    fn bps_patch(source: &[u8], target: &[u8]) -> Vec<u8> {
+        let half = target.len() / 2;
+        let mut patch = b"BPS1".to_vec();
+        varint(source.len(), &mut patch);
+        varint(target.len(), &mut patch);
+        varint(0, &mut patch);
+
+        varint((half - 1) << 2, &mut patch);
+        varint(((target.len() - half - 1) << 2) | 1, &mut patch);
+        patch.extend_from_slice(&target[half..]);
+
+        footer(&mut patch, source, target);
+        patch
+    }
+
+    fn target() -> Vec<u8> {
+        let mut target = rom();
+        target[40..].fill(0xAA);
+        target
+    }
+
+    #[test]
+    fn unknown_format() {
+        assert_eq!(
+            patch_apply(&rom(), b"NOTAPATCH"),
+            Err(PatchError::UnknownFormat)
+        );
+    }
+
+    #[test]
+    fn ips_records_rle_and_truncate() {
+        let mut patch = b"PATCH".to_vec();
+        // 2 bytes at 0x000004
+        patch.extend_from_slice(&[0x00, 0x00, 0x04, 0x00, 0x02, 0xDE, 0xAD]);
+        // RLE run of 3 x 0x55 at 0x000010
+        patch.extend_from_slice(&[0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x03, 0x55]);
+        patch.extend_from_slice(b"EOF");
+        // Truncate to 32 bytes
+        patch.extend_from_slice(&[0x00, 0x00, 0x20]);
+
+        let mut expected = rom();
+        expected[4..6].copy_from_slice(&[0xDE, 0xAD]);
+        expected[0x10..0x13].fill(0x55);
+        expected.truncate(32);
+
+        assert_eq!(patch_apply(&rom(), &patch), Ok(expected));
+    }
+
+    #[test]
+    fn ips_grows_rom() {
+        let mut patch = b"PATCH".to_vec();
+        patch.extend_from_slice(&[0x00, 0x00, 0x48, 0x00, 0x00, 0x00, 0x04, 0x11]);
+        patch.extend_from_slice(b"EOF");
+
+        let patched = patch_apply(&rom(), &patch).unwrap();
+        assert_eq!(patched.len(), 0x4C);
+        assert_eq!(&patched[0x40..0x48], &[0; 8]);
+        assert_eq!(&patched[0x48..], &[0x11; 4]);
+    }
+
+    #[test]
+    fn ips_truncated() {
+        let mut patch = b"PATCH".to_vec();
+        patch.extend_from_slice(&[0x00, 0x00, 0x04, 0x00, 0x08, 0x01]);
+
+        assert_eq!(
+            patch_apply(&rom(), &patch),
+            Err(PatchError::Truncated(PatchFormat::IPS))
+        );
+    }
+
+    #[test]
+    fn ups_applies() {
+        let patch = ups_patch(&rom(), &target());
+        assert_eq!(patch_apply(&rom(), &patch), Ok(target()));
+    }
+
+    #[test]
+    fn ups_source_crc_mismatch() {
+        let patch = ups_patch(&rom(), &target());
+        let mut other = rom();
+        other[0] ^= 1;
+
+        assert_eq!(
+            patch_apply(&other, &patch),
+            Err(PatchError::BadSourceCrc {
+                expected: crc32fast::hash(&rom()),
+                actual: crc32fast::hash(&other),
+            })
+        );
+    }
+
+    #[test]
+    fn ups_source_size_mismatch() {
+        let patch = ups_patch(&rom(), &target());
+
+        assert_eq!(
+            patch_apply(&rom()[..32], &patch),
+            Err(PatchError::SourceSizeMismatch {
+                expected: 64,
+                actual: 32
+            })
+        );
+    }
+
+    #[test]
+    fn ups_target_crc_mismatch() {
+        let mut wrong = target();
+        wrong[0] ^= 1;
+
+        // Claims a target other than the one the hunks produce
+        let mut patch = ups_patch(&rom(), &target());
+        patch.truncate(patch.len() - 12);
+        footer(&mut patch, &rom(), &wrong);
+
+        assert_eq!(
+            patch_apply(&rom(), &patch),
+            Err(PatchError::BadTargetCrc {
+                expected: crc32fast::hash(&wrong),
+                actual: crc32fast::hash(&target()),
+            })
+        );
+    }
+
+    #[test]
+    fn bad_patch_crc() {
+        let mut patch = ups_patch(&rom(), &target());
+        let last = patch.len() - 1;
+        patch[last] ^= 1;
+
+        assert!(matches!(
+            patch_apply(&rom(), &patch),
+            Err(PatchError::BadPatchCrc { .. })
+        ));
+    }
+
+    #[test]
+    fn bps_applies() {
+        let patch = bps_patch(&rom(), &target());
+        assert_eq!(patch_apply(&rom(), &patch), Ok(target()));
+    }
+
+    #[test]
+    fn bps_source_crc_mismatch() {
+        let patch = bps_patch(&rom(), &target());
+        let other = vec![0; 64];
+
+        assert_eq!(
+            patch_apply(&other, &patch),
+            Err(PatchError::BadSourceCrc {
+                expected: crc32fast::hash(&rom()),
+                actual: crc32fast::hash(&other),
+            })
+        );
+    }
+
+    #[test]
+    fn bps_source_size_mismatch() {
+        let patch = bps_patch(&rom(), &target());
+
+        assert_eq!(
+            patch_apply(&[0; 128], &patch),
+            Err(PatchError::SourceSizeMismatch {
+                expected: 64,
+                actual: 128
+            })
+        );
+    }
+
+    #[test]
+    fn bps_target_crc_mismatch() {
+        let wrong = vec![0; 64];
+
+        let mut patch = bps_patch(&rom(), &target());
+        patch.truncate(patch.len() - 12);
+        footer(&mut patch, &rom(), &wrong);
+
+        assert_eq!(
+            patch_apply(&rom(), &patch),
+            Err(PatchError::BadTargetCrc {
+                expected: crc32fast::hash(&wrong),
+                actual: crc32fast::hash(&target()),
+            })
+        );
+    }
+
+    #[test]
+    fn bps_source_copy_out_of_range() {
+        let mut patch = b"BPS1".to_vec();
+        varint(64, &mut patch);
+        varint(8, &mut patch);
+        varint(0, &mut patch);
+
+        // SourceCopy of 8 bytes from offset 60
+        varint((7 << 2) | 2, &mut patch);
+        varint(60 << 1, &mut patch);
+        footer(&mut patch, &rom(), &[0; 8]);
+
+        assert_eq!(
+            patch_apply(&rom(), &patch),
+            Err(PatchError::OutOfRange(PatchFormat::BPS))
+        );
+    }
+}
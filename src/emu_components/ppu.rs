@@ -1,3 +1,227 @@
-pub fn ppu_init() {}
+use super::boot::boot_rom_mapped;
+use super::common::bit;
+use super::dma::dma_transferring;
+use super::lcd::{lcd_get_context, lcd_get_mode, lcd_set_mode, LcdMode};
+use super::ppu_sm::{
+    ppu_mode_hblank, ppu_mode_oam, ppu_mode_vblank, ppu_mode_xfer, ppu_update_stat,
+};
 
-pub fn ppu_tick() {}
+/*
+  0x8000 - 0x9FFF : VRAM, two switchable banks on CGB (VBK, 0xFF4F)
+  0xFE00 - 0xFE9F : OAM, 40 entries of 4 bytes (Y, X, tile, flags)
+
+  The CPU can't access VRAM while the PPU is drawing (mode 3), nor OAM
+  while the PPU is scanning or drawing (modes 2 and 3). Blocked reads
+  return 0xFF and blocked writes are dropped.
+
+  Renderers, selected with --renderer=<name>:
+
+  fifo     : pixel FIFO, mode 3 gets longer with fine scrolling, the window
+             and sprites, and register writes during mode 3 land on the
+             pixels drawn after them
+  scanline : each line drawn at once as a fixed length mode 3 ends, faster
+*/
+
+pub const LINES_PER_FRAME: u8 = 154;
+pub const TICKS_PER_LINE: u32 = 456;
+pub const XRES: u8 = 160;
+pub const YRES: u8 = 144;
+
+// DMG shades for color indices 0-3 after the palette, ARGB8888
+pub const DMG_COLORS: [u32; 4] = [0xFFFFFFFF, 0xFFAAAAAA, 0xFF555555, 0xFF000000];
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PpuRenderer {
+    RENDERER_FIFO,
+    RENDERER_SCANLINE,
+}
+
+impl PpuRenderer {
+    pub fn from_name(name: &str) -> Option<PpuRenderer> {
+        match name.to_ascii_lowercase().as_str() {
+            "fifo" => Some(PpuRenderer::RENDERER_FIFO),
+            "scanline" => Some(PpuRenderer::RENDERER_SCANLINE),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PpuContext {
+    pub vram: [u8; 0x4000],
+    pub vram_bank: u8,
+    pub oam_ram: [u8; 0xA0],
+
+    // Current scanline, 0-153, and dot within it, 0-455
+    pub line: u8,
+    pub line_ticks: u32,
+
+    pub current_frame: u32,
+    pub renderer: PpuRenderer,
+    pub lcd_on: bool,
+    pub stat_line: bool,
+
+    // OAM indices of the up to 10 sprites found by the OAM scan for the current line
+    pub line_sprites: [u8; 10],
+    pub line_sprite_count: usize,
+
+    // Set once LY matched WY this frame, window_line only counts the lines the window was drawn on
+    pub window_triggered: bool,
+    pub window_line: u8,
+
+    // 160x144 ARGB8888, a row is written as soon as its line has been drawn
+    pub video_buffer: [u32; XRES as usize * YRES as usize],
+}
+
+static mut CTX: PpuContext = PpuContext {
+    vram: [0; 0x4000],
+    vram_bank: 0,
+    oam_ram: [0; 0xA0],
+    line: 0,
+    line_ticks: 0,
+    current_frame: 0,
+    renderer: PpuRenderer::RENDERER_FIFO,
+    lcd_on: false,
+    stat_line: false,
+    line_sprites: [0; 10],
+    line_sprite_count: 0,
+    window_triggered: false,
+    window_line: 0,
+    video_buffer: [DMG_COLORS[0]; XRES as usize * YRES as usize],
+};
+
+pub unsafe fn ppu_get_context() -> &'static mut PpuContext {
+    &mut CTX
+}
+
+/*
+  The boot ROM hands over near the end of the last VBlank line, where LY
+  already reads 0. At power-on the LCD is off and the PPU sits at the
+  start of line 0.
+*/
+pub unsafe fn ppu_init() {
+    CTX.vram_bank = 0;
+
+    if boot_rom_mapped() {
+        CTX.line = 0;
+        CTX.line_ticks = 0;
+        lcd_set_mode(LcdMode::MODE_HBLANK);
+    } else {
+        CTX.line = 153;
+        CTX.line_ticks = 400;
+        lcd_set_mode(LcdMode::MODE_VBLANK);
+    }
+
+    lcd_get_context().ly = 0;
+    CTX.lcd_on = bit(lcd_get_context().lcdc, 7);
+    CTX.stat_line = false;
+    CTX.current_frame = 0;
+    CTX.line_sprite_count = 0;
+    CTX.window_triggered = false;
+    CTX.window_line = 0;
+    CTX.video_buffer.fill(DMG_COLORS[0]);
+}
+
+pub unsafe fn ppu_set_renderer(renderer: PpuRenderer) {
+    CTX.renderer = renderer;
+}
+
+// Turning the LCD off resets LY and the mode and blanks the screen, turning it on restarts a frame
+unsafe fn ppu_lcd_switch(on: bool) {
+    CTX.lcd_on = on;
+    CTX.line = 0;
+    CTX.line_ticks = 0;
+    CTX.stat_line = false;
+    CTX.window_triggered = false;
+    CTX.window_line = 0;
+    lcd_get_context().ly = 0;
+
+    if !on {
+        CTX.video_buffer.fill(DMG_COLORS[0]);
+    }
+
+    lcd_set_mode(if on {
+        LcdMode::MODE_OAM
+    } else {
+        LcdMode::MODE_HBLANK
+    });
+}
+
+// One dot, called 4 times per M-cycle (2 in CGB double speed)
+pub unsafe fn ppu_tick() {
+    let on = bit(lcd_get_context().lcdc, 7);
+
+    if on != CTX.lcd_on {
+        ppu_lcd_switch(on);
+    }
+
+    if !on {
+        return;
+    }
+
+    CTX.line_ticks += 1;
+
+    match lcd_get_mode() {
+        LcdMode::MODE_OAM => ppu_mode_oam(),
+        LcdMode::MODE_XFER => ppu_mode_xfer(),
+        LcdMode::MODE_HBLANK => ppu_mode_hblank(),
+        LcdMode::MODE_VBLANK => ppu_mode_vblank(),
+    }
+
+    ppu_update_stat();
+}
+
+unsafe fn vram_accessible() -> bool {
+    lcd_get_mode() != LcdMode::MODE_XFER
+}
+
+pub unsafe fn oam_accessible() -> bool {
+    !dma_transferring() && !matches!(lcd_get_mode(), LcdMode::MODE_OAM | LcdMode::MODE_XFER)
+}
+
+pub unsafe fn ppu_vram_read(address: u16) -> u8 {
+    if !vram_accessible() {
+        return 0xFF;
+    }
+
+    CTX.vram[(CTX.vram_bank as usize) * 0x2000 + (address - 0x8000) as usize]
+}
+
+pub unsafe fn ppu_vram_write(address: u16, value: u8) {
+    if !vram_accessible() {
+        return;
+    }
+
+    CTX.vram[(CTX.vram_bank as usize) * 0x2000 + (address - 0x8000) as usize] = value;
+}
+
+pub unsafe fn ppu_oam_read(address: u16) -> u8 {
+    if !oam_accessible() {
+        return 0xFF;
+    }
+
+    CTX.oam_ram[(address - 0xFE00) as usize]
+}
+
+pub unsafe fn ppu_oam_write(address: u16, value: u8) {
+    if !oam_accessible() {
+        return;
+    }
+
+    CTX.oam_ram[(address - 0xFE00) as usize] = value;
+}
+
+// DMA writes go straight into OAM regardless of the PPU mode
+pub unsafe fn ppu_oam_dma_write(index: u8, value: u8) {
+    CTX.oam_ram[index as usize] = value;
+}
+
+// 0xFF4F VBK, only bit 0 is used
+pub unsafe fn ppu_vram_bank_read() -> u8 {
+    0xFE | CTX.vram_bank
+}
+
+pub unsafe fn ppu_vram_bank_write(value: u8) {
+    CTX.vram_bank = value & 1;
+}
@@ -0,0 +1,316 @@
+use super::common::bit;
+use super::emu::emu_cgb_mode;
+use super::lcd::{
+    lcd_get_context, lcdc_bg_map_area, lcdc_bgw_tile_address, lcdc_obj_enable, lcdc_win_map_area,
+};
+use super::ppu::{ppu_get_context, XRES};
+use super::ppu_render::{
+    ppu_mix_pixel, ppu_sprite_row, ppu_window_visible, tile_color, vram_byte, BgPixel,
+};
+use std::collections::VecDeque;
+
+/*
+  Pixel FIFO renderer, advanced one dot at a time through mode 3.
+
+  The BG fetcher takes 2 dots for each of its steps, reading the tile
+  number, the low and the high byte of the tile row, then waits until the
+  BG FIFO is empty to push the 8 pixels. One pixel is shifted out of the
+  FIFO per dot, mixed with the sprite FIFO and written to the frame buffer
+  with the palettes as they are at that dot.
+
+  Mode 3 lasts 172 dots plus:
+
+  SCX & 7 : pixels of the first tile thrown away
+  6       : the fetcher restarting on the window when X reaches WX - 7
+  6 - 11  : per sprite, the BG fetcher finishes its current tile before
+            the 6 dot sprite row fetch, nothing is shifted out meanwhile
+
+  The first tile fetch of every line is thrown away, which makes up the
+  12 dots between 160 pixels and the 172 dot minimum.
+*/
+
+const SPRITE_FETCH_TICKS: u8 = 6;
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FetchState {
+    FS_TILE,
+    FS_DATA0,
+    FS_DATA1,
+    FS_PUSH,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ObjPixel {
+    pub color: u8,
+    pub flags: u8,
+    pub index: u8,
+}
+
+#[derive(Debug)]
+pub struct PipelineContext {
+    pub fetch_state: FetchState,
+    pub fetch_ticks: u8,
+    // Tile column being fetched, counted from the line or window start
+    pub fetch_x: u8,
+    pub fetch_window: bool,
+    pub dummy_fetch: bool,
+    pub tile: u8,
+    pub attributes: u8,
+    pub data_lo: u8,
+    pub data_hi: u8,
+
+    pub bg_fifo: VecDeque<BgPixel>,
+    pub obj_fifo: VecDeque<ObjPixel>,
+
+    // Pixels still to be dropped for SCX, then X of the next pixel shifted out
+    pub discard: u8,
+    pub line_x: u8,
+
+    // Per entry of the line's sprites, the one being fetched and the dots that fetch has left
+    pub sprite_fetched: [bool; 10],
+    pub sprite_pending: Option<usize>,
+    pub sprite_ticks: u8,
+}
+
+static mut CTX: PipelineContext = PipelineContext {
+    fetch_state: FetchState::FS_TILE,
+    fetch_ticks: 0,
+    fetch_x: 0,
+    fetch_window: false,
+    dummy_fetch: false,
+    tile: 0,
+    attributes: 0,
+    data_lo: 0,
+    data_hi: 0,
+    bg_fifo: VecDeque::new(),
+    obj_fifo: VecDeque::new(),
+    discard: 0,
+    line_x: 0,
+    sprite_fetched: [false; 10],
+    sprite_pending: None,
+    sprite_ticks: 0,
+};
+
+// Called as mode 3 starts
+pub unsafe fn ppu_pipeline_start() {
+    CTX.fetch_state = FetchState::FS_TILE;
+    CTX.fetch_ticks = 0;
+    CTX.fetch_x = 0;
+    CTX.fetch_window = false;
+    CTX.dummy_fetch = true;
+    CTX.bg_fifo.clear();
+    CTX.obj_fifo.clear();
+    CTX.discard = lcd_get_context().scx & 7;
+    CTX.line_x = 0;
+    CTX.sprite_fetched = [false; 10];
+    CTX.sprite_pending = None;
+    CTX.sprite_ticks = 0;
+}
+
+// Tile row of the current fetch, Y flipped by the CGB attributes
+unsafe fn fetch_data_address() -> u16 {
+    let lcd = lcd_get_context();
+
+    let mut row = if CTX.fetch_window {
+        ppu_get_context().window_line % 8
+    } else {
+        lcd.ly.wrapping_add(lcd.scy) % 8
+    };
+
+    if bit(CTX.attributes, 6) {
+        row = 7 - row;
+    }
+
+    lcdc_bgw_tile_address(CTX.tile) + row as u16 * 2
+}
+
+unsafe fn fetch_tile() {
+    let lcd = lcd_get_context();
+
+    // SCX and SCY are read again for every tile, only fine X scrolling is latched at the line start
+    let map_address = if CTX.fetch_window {
+        let y = ppu_get_context().window_line;
+        lcdc_win_map_area() + (y as u16 / 8) * 32 + (CTX.fetch_x & 31) as u16
+    } else {
+        let y = lcd.ly.wrapping_add(lcd.scy);
+        let x = (lcd.scx / 8).wrapping_add(CTX.fetch_x) & 31;
+        lcdc_bg_map_area() + (y as u16 / 8) * 32 + x as u16
+    };
+
+    CTX.tile = vram_byte(0, map_address);
+    CTX.attributes = if emu_cgb_mode() {
+        vram_byte(1, map_address)
+    } else {
+        0
+    };
+}
+
+unsafe fn fetch_push() {
+    for x in 0..8 {
+        let col = if bit(CTX.attributes, 5) { 7 - x } else { x };
+
+        CTX.bg_fifo.push_back(BgPixel {
+            color: tile_color(CTX.data_lo, CTX.data_hi, col),
+            palette: CTX.attributes & 0b111,
+            priority: bit(CTX.attributes, 7),
+        });
+    }
+}
+
+// The push shares its dot with the first dot of the next tile fetch
+unsafe fn fetcher_tick() {
+    if CTX.fetch_state == FetchState::FS_PUSH {
+        if !CTX.bg_fifo.is_empty() {
+            return;
+        }
+
+        fetch_push();
+        CTX.fetch_x = CTX.fetch_x.wrapping_add(1);
+        CTX.fetch_state = FetchState::FS_TILE;
+    }
+
+    let bank = (CTX.attributes >> 3) & 1;
+
+    CTX.fetch_ticks += 1;
+    if CTX.fetch_ticks < 2 {
+        return;
+    }
+    CTX.fetch_ticks = 0;
+
+    match CTX.fetch_state {
+        FetchState::FS_TILE => {
+            fetch_tile();
+            CTX.fetch_state = FetchState::FS_DATA0;
+        }
+        FetchState::FS_DATA0 => {
+            CTX.data_lo = vram_byte(bank, fetch_data_address());
+            CTX.fetch_state = FetchState::FS_DATA1;
+        }
+        FetchState::FS_DATA1 => {
+            CTX.data_hi = vram_byte(bank, fetch_data_address() + 1);
+
+            if CTX.dummy_fetch {
+                CTX.dummy_fetch = false;
+                CTX.fetch_state = FetchState::FS_TILE;
+            } else {
+                CTX.fetch_state = FetchState::FS_PUSH;
+            }
+        }
+        FetchState::FS_PUSH => (),
+    }
+}
+
+/*
+  Merges a fetched sprite row into the sprite FIFO. Pixels left of the
+  screen are cut off. A pixel already in the FIFO stays unless it is
+  transparent, or in CGB mode comes from a sprite with a higher OAM index.
+*/
+unsafe fn sprite_merge(index: u8) {
+    let sprite = ppu_sprite_row(index);
+    let skip = (CTX.line_x + 8).saturating_sub(sprite.x);
+    let cgb = emu_cgb_mode();
+
+    while CTX.obj_fifo.len() < 8 {
+        CTX.obj_fifo.push_back(ObjPixel {
+            color: 0,
+            flags: 0,
+            index: 0xFF,
+        });
+    }
+
+    for x in skip..8 {
+        let col = if bit(sprite.flags, 5) { 7 - x } else { x };
+        let color = tile_color(sprite.lo, sprite.hi, col);
+        let slot = &mut CTX.obj_fifo[(x - skip) as usize];
+
+        if color != 0 && (slot.color == 0 || (cgb && index < slot.index)) {
+            *slot = ObjPixel {
+                color,
+                flags: sprite.flags,
+                index,
+            };
+        }
+    }
+}
+
+// The next sprite from the OAM scan that starts at the current X, in OAM order
+unsafe fn sprite_check() -> Option<usize> {
+    let ppu = ppu_get_context();
+
+    (0..ppu.line_sprite_count).find(|i| {
+        let x = ppu.oam_ram[ppu.line_sprites[*i] as usize * 4 + 1];
+        !CTX.sprite_fetched[*i] && x <= CTX.line_x + 8
+    })
+}
+
+// One dot of mode 3, returns true once the 160th pixel has been shifted out
+pub unsafe fn ppu_pipeline_process() -> bool {
+    if CTX.sprite_ticks > 0 {
+        CTX.sprite_ticks -= 1;
+
+        if CTX.sprite_ticks > 0 {
+            return false;
+        }
+
+        let i = CTX.sprite_pending.take().unwrap();
+        CTX.sprite_fetched[i] = true;
+        sprite_merge(ppu_get_context().line_sprites[i]);
+    }
+
+    if !CTX.fetch_window
+        && CTX.discard == 0
+        && ppu_window_visible()
+        && CTX.line_x as u16 + 7 >= lcd_get_context().wx as u16
+    {
+        CTX.fetch_window = true;
+        CTX.fetch_x = 0;
+        CTX.fetch_ticks = 0;
+        CTX.fetch_state = FetchState::FS_TILE;
+        CTX.bg_fifo.clear();
+    }
+
+    if CTX.sprite_pending.is_none() && CTX.discard == 0 && lcdc_obj_enable() {
+        CTX.sprite_pending = sprite_check();
+    }
+
+    fetcher_tick();
+
+    if CTX.sprite_pending.is_some() {
+        // The sprite row is fetched once the BG fetcher has a tile ready and pixels are waiting
+        if CTX.fetch_state == FetchState::FS_PUSH && !CTX.bg_fifo.is_empty() {
+            CTX.sprite_ticks = SPRITE_FETCH_TICKS;
+        }
+        return false;
+    }
+
+    let bg = match CTX.bg_fifo.pop_front() {
+        Some(bg) => bg,
+        None => return false,
+    };
+
+    if CTX.discard > 0 {
+        CTX.discard -= 1;
+        return false;
+    }
+
+    let sprite = match CTX.obj_fifo.pop_front() {
+        Some(obj) if obj.color != 0 && lcdc_obj_enable() => Some((obj.color, obj.flags)),
+        _ => None,
+    };
+
+    let pixel = ppu_mix_pixel(bg, sprite);
+    let row = lcd_get_context().ly as usize * XRES as usize;
+    ppu_get_context().video_buffer[row + CTX.line_x as usize] = pixel;
+    CTX.line_x += 1;
+
+    if CTX.line_x < XRES {
+        return false;
+    }
+
+    if CTX.fetch_window {
+        ppu_get_context().window_line += 1;
+    }
+
+    true
+}
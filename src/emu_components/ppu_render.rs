@@ -0,0 +1,319 @@
+use super::common::bit;
+use super::emu::{emu_cgb_mode, emu_model};
+use super::lcd::{
+    lcd_get_context, lcdc_bg_map_area, lcdc_bgw_enable, lcdc_bgw_tile_address, lcdc_obj_enable,
+    lcdc_obj_height, lcdc_win_enable, lcdc_win_map_area,
+};
+use super::ppu::{ppu_get_context, DMG_COLORS, XRES};
+
+/*
+  Scanline renderer, draws the whole of the current line into the frame
+  buffer as mode 3 ends. The OAM scan, sprite row fetch and pixel mixing
+  are shared with the FIFO renderer in ppu_pipeline.
+
+  BG     : 32x32 tile map scrolled by SCX/SCY, wrapping around
+  Window : drawn from X = WX - 7 to the right edge, using its own line
+           counter rather than LY
+  OBJ    : the sprites picked by the OAM scan. On DMG the smaller X wins
+           where sprites overlap, then the lower OAM index, in CGB mode
+           only the OAM index counts.
+
+  OAM flags / CGB BG map attributes (VRAM bank 1):
+
+  7   : BG colors 1-3 drawn over the sprite
+  6   : Y flip
+  5   : X flip
+  4   : DMG palette, OBP0 / OBP1 (OAM only)
+  3   : CGB VRAM bank
+  0-2 : CGB palette
+*/
+
+const MAX_LINE_SPRITES: usize = 10;
+
+#[derive(Debug, Clone, Copy)]
+pub struct BgPixel {
+    pub color: u8,
+    pub palette: u8,
+    pub priority: bool,
+}
+
+// A sprite's OAM X and flags with the tile row it shows on the current line
+#[derive(Debug, Clone, Copy)]
+pub struct LineSprite {
+    pub x: u8,
+    pub flags: u8,
+    pub lo: u8,
+    pub hi: u8,
+}
+
+pub unsafe fn vram_byte(bank: u8, address: u16) -> u8 {
+    ppu_get_context().vram[bank as usize * 0x2000 + (address - 0x8000) as usize]
+}
+
+// Color index 0-3 of pixel x (0 = leftmost) of a tile row
+pub fn tile_color(lo: u8, hi: u8, x: u8) -> u8 {
+    let n = 7 - x;
+    ((hi >> n) & 1) << 1 | ((lo >> n) & 1)
+}
+
+/*
+  DMG palettes map a color index to one of 4 shades. On CGB hardware in
+  DMG compatibility mode the shade picks a color out of CGB palette RAM
+  instead: BG palette 0 for BGP, OBJ palettes 0 and 1 for OBP0 and OBP1.
+*/
+unsafe fn dmg_color(palette: u8, color: u8, compat_ram: &[u8; 64], compat_palette: u8) -> u32 {
+    let shade = (palette >> (color * 2)) & 0b11;
+
+    if emu_model().is_cgb() {
+        cgb_color(compat_ram, compat_palette, shade)
+    } else {
+        DMG_COLORS[shade as usize]
+    }
+}
+
+// CGB palette RAM holds 8 palettes of 4 little endian RGB555 colors
+fn cgb_color(palette_ram: &[u8; 64], palette: u8, color: u8) -> u32 {
+    let index = (palette as usize * 4 + color as usize) * 2;
+    let value = palette_ram[index] as u32 | (palette_ram[index + 1] as u32) << 8;
+
+    let scale = |c: u32| (c << 3) | (c >> 2);
+    let r = scale(value & 0x1F);
+    let g = scale((value >> 5) & 0x1F);
+    let b = scale((value >> 10) & 0x1F);
+
+    0xFF000000 | r << 16 | g << 8 | b
+}
+
+// Mode 2: the first 10 sprites in OAM order that cover LY, regardless of their X
+pub unsafe fn ppu_oam_scan() {
+    let ctx = ppu_get_context();
+    let height = lcdc_obj_height();
+    let line = lcd_get_context().ly as u16 + 16;
+
+    ctx.line_sprite_count = 0;
+
+    for index in 0..40u8 {
+        if ctx.line_sprite_count == MAX_LINE_SPRITES {
+            break;
+        }
+
+        let y = ctx.oam_ram[index as usize * 4] as u16;
+
+        if line >= y && line < y + height as u16 {
+            ctx.line_sprites[ctx.line_sprite_count] = index;
+            ctx.line_sprite_count += 1;
+        }
+    }
+}
+
+unsafe fn bg_pixel(x: u8, window_x: Option<u8>) -> BgPixel {
+    let lcd = lcd_get_context();
+
+    let (map, px, py) = match window_x {
+        Some(wx) => (
+            lcdc_win_map_area(),
+            x + 7 - wx,
+            ppu_get_context().window_line,
+        ),
+        None => (
+            lcdc_bg_map_area(),
+            x.wrapping_add(lcd.scx),
+            lcd.ly.wrapping_add(lcd.scy),
+        ),
+    };
+
+    let map_address = map + (py as u16 / 8) * 32 + px as u16 / 8;
+    let tile = vram_byte(0, map_address);
+    let attributes = if emu_cgb_mode() {
+        vram_byte(1, map_address)
+    } else {
+        0
+    };
+
+    let mut row = py % 8;
+    let mut col = px % 8;
+
+    if bit(attributes, 6) {
+        row = 7 - row;
+    }
+
+    if bit(attributes, 5) {
+        col = 7 - col;
+    }
+
+    let bank = (attributes >> 3) & 1;
+    let address = lcdc_bgw_tile_address(tile) + row as u16 * 2;
+
+    BgPixel {
+        color: tile_color(vram_byte(bank, address), vram_byte(bank, address + 1), col),
+        palette: attributes & 0b111,
+        priority: bit(attributes, 7),
+    }
+}
+
+// Fetches the tile row of OAM entry index for the current line
+pub unsafe fn ppu_sprite_row(index: u8) -> LineSprite {
+    let ctx = ppu_get_context();
+    let height = lcdc_obj_height();
+    let line = lcd_get_context().ly.wrapping_add(16);
+
+    let entry = &ctx.oam_ram[index as usize * 4..index as usize * 4 + 4];
+    let flags = entry[3];
+
+    let mut row = line.wrapping_sub(entry[0]) % height;
+    if bit(flags, 6) {
+        row = height - 1 - row;
+    }
+
+    let tile = if height == 16 {
+        entry[2] & 0xFE
+    } else {
+        entry[2]
+    };
+    let bank = if emu_cgb_mode() { (flags >> 3) & 1 } else { 0 };
+    let address = 0x8000 + tile as u16 * 16 + row as u16 * 2;
+
+    LineSprite {
+        x: entry[1],
+        flags,
+        lo: vram_byte(bank, address),
+        hi: vram_byte(bank, address + 1),
+    }
+}
+
+// Fetches the tile row of every sprite on the line, in drawing priority order
+unsafe fn line_sprites(sprites: &mut [LineSprite; MAX_LINE_SPRITES]) -> usize {
+    let ctx = ppu_get_context();
+
+    let mut order = ctx.line_sprites;
+    let count = ctx.line_sprite_count;
+    if !emu_cgb_mode() {
+        // Sprites at the same X stay in OAM order
+        order[..count].sort_unstable_by_key(|index| (ctx.oam_ram[*index as usize * 4 + 1], *index));
+    }
+
+    for (i, index) in order[..count].iter().enumerate() {
+        sprites[i] = ppu_sprite_row(*index);
+    }
+
+    count
+}
+
+/*
+  The first sprite in priority order with a non transparent pixel at x,
+  as its color index and flags. A sprite hidden behind the BG
+  still hides the sprites below it.
+*/
+fn sprite_pixel(sprites: &[LineSprite], x: u8) -> Option<(u8, u8)> {
+    let screen_x = x as u16 + 8;
+
+    for sprite in sprites {
+        let sx = sprite.x as u16;
+
+        if screen_x < sx || screen_x >= sx + 8 {
+            continue;
+        }
+
+        let mut col = (screen_x - sx) as u8;
+        if bit(sprite.flags, 5) {
+            col = 7 - col;
+        }
+
+        let color = tile_color(sprite.lo, sprite.hi, col);
+        if color != 0 {
+            return Some((color, sprite.flags));
+        }
+    }
+
+    None
+}
+
+// On DMG LCDC bit 0 blanks BG and window, in CGB mode it only takes away their priority
+unsafe fn bgw_enable() -> bool {
+    emu_cgb_mode() || lcdc_bgw_enable()
+}
+
+// True if the window covers part of the current line
+pub unsafe fn ppu_window_visible() -> bool {
+    bgw_enable()
+        && lcdc_win_enable()
+        && ppu_get_context().window_triggered
+        && lcd_get_context().wx <= 166
+}
+
+/*
+  Final color of a pixel from its BG/window pixel and the sprite pixel
+  that won among the sprites (color index and OAM flags), using the
+  palettes as they are when the pixel is shifted out.
+*/
+pub unsafe fn ppu_mix_pixel(bg: BgPixel, sprite: Option<(u8, u8)>) -> u32 {
+    let lcd = lcd_get_context();
+    let cgb = emu_cgb_mode();
+    let bgw_enable = bgw_enable();
+    let bg_priority = !cgb || lcdc_bgw_enable();
+    let bg_color = if bgw_enable { bg.color } else { 0 };
+
+    if let Some((color, flags)) = sprite {
+        let behind_bg = bg_priority && bg_color != 0 && (bit(flags, 7) || bg.priority);
+
+        if !behind_bg {
+            return if cgb {
+                cgb_color(&lcd.obj_palette_ram, flags & 0b111, color)
+            } else if bit(flags, 4) {
+                dmg_color(lcd.obp1, color, &lcd.obj_palette_ram, 1)
+            } else {
+                dmg_color(lcd.obp0, color, &lcd.obj_palette_ram, 0)
+            };
+        }
+    }
+
+    if !bgw_enable {
+        dmg_color(0, 0, &lcd.bg_palette_ram, 0)
+    } else if cgb {
+        cgb_color(&lcd.bg_palette_ram, bg.palette, bg.color)
+    } else {
+        dmg_color(lcd.bgp, bg.color, &lcd.bg_palette_ram, 0)
+    }
+}
+
+// Called as mode 3 ends, with the registers as they are at that point
+pub unsafe fn ppu_render_line() {
+    let lcd = lcd_get_context();
+    let bgw_enable = bgw_enable();
+    let window_visible = ppu_window_visible();
+
+    let mut sprites = [LineSprite {
+        x: 0,
+        flags: 0,
+        lo: 0,
+        hi: 0,
+    }; MAX_LINE_SPRITES];
+    let sprite_count = if lcdc_obj_enable() {
+        line_sprites(&mut sprites)
+    } else {
+        0
+    };
+
+    let row = lcd.ly as usize * XRES as usize;
+
+    for x in 0..XRES {
+        let bg = if !bgw_enable {
+            BgPixel {
+                color: 0,
+                palette: 0,
+                priority: false,
+            }
+        } else if window_visible && x as u16 + 7 >= lcd.wx as u16 {
+            bg_pixel(x, Some(lcd.wx))
+        } else {
+            bg_pixel(x, None)
+        };
+
+        let sprite = sprite_pixel(&sprites[..sprite_count], x);
+        ppu_get_context().video_buffer[row + x as usize] = ppu_mix_pixel(bg, sprite);
+    }
+
+    if window_visible {
+        ppu_get_context().window_line += 1;
+    }
+}
@@ -0,0 +1,149 @@
+use super::common::{bit, bit_set};
+use super::interrupts::{cpu_request_interrupt, InterruptType};
+use super::lcd::{lcd_get_context, lcd_get_mode, lcd_set_mode, LcdMode};
+use super::ppu::{ppu_get_context, PpuRenderer, LINES_PER_FRAME, TICKS_PER_LINE, YRES};
+use super::ppu_pipeline::{ppu_pipeline_process, ppu_pipeline_start};
+use super::ppu_render::{ppu_oam_scan, ppu_render_line};
+
+/*
+  PPU mode state machine, advanced one dot at a time by ppu_tick:
+
+  Lines 0 - 143   : mode 2 (OAM scan) for 80 dots, mode 3 (drawing) for
+                    172 dots or more, then mode 0 (HBlank) until dot 455
+  Lines 144 - 153 : mode 1 (VBlank), the VBlank interrupt is requested
+                    when line 144 starts
+
+  LY already reads 0 from dot 4 of line 153, including for the LYC compare.
+
+  The OAM scan picks the line's sprites as mode 2 ends. The FIFO renderer
+  then draws a pixel per dot and ends mode 3 after the last one, the
+  scanline renderer draws the whole line after a fixed 172 dots.
+
+  The four STAT interrupt sources (HBlank, VBlank, OAM scan, LY=LYC) are
+  ORed into a single STAT line and the interrupt is only requested when
+  that line goes from low to high, so a source becoming active while
+  another one already holds the line high is lost.
+*/
+
+const OAM_TICKS: u32 = 80;
+const XFER_TICKS: u32 = 172;
+
+unsafe fn increment_ly() {
+    let ctx = ppu_get_context();
+
+    ctx.line += 1;
+    ctx.line_ticks = 0;
+
+    if ctx.line >= LINES_PER_FRAME {
+        ctx.line = 0;
+    }
+
+    lcd_get_context().ly = ctx.line;
+}
+
+// The window is armed by LY matching WY on any line of the frame
+unsafe fn window_check() {
+    let ctx = ppu_get_context();
+
+    if ctx.line == 0 {
+        ctx.window_triggered = false;
+        ctx.window_line = 0;
+    }
+
+    if lcd_get_context().wy == ctx.line {
+        ctx.window_triggered = true;
+    }
+}
+
+pub unsafe fn ppu_mode_oam() {
+    if ppu_get_context().line_ticks >= OAM_TICKS {
+        window_check();
+        ppu_oam_scan();
+
+        if ppu_get_context().renderer == PpuRenderer::RENDERER_FIFO {
+            ppu_pipeline_start();
+        }
+
+        lcd_set_mode(LcdMode::MODE_XFER);
+    }
+}
+
+pub unsafe fn ppu_mode_xfer() {
+    let done = match ppu_get_context().renderer {
+        PpuRenderer::RENDERER_FIFO => ppu_pipeline_process(),
+        PpuRenderer::RENDERER_SCANLINE => {
+            if ppu_get_context().line_ticks >= OAM_TICKS + XFER_TICKS {
+                ppu_render_line();
+                true
+            } else {
+                false
+            }
+        }
+    };
+
+    if done {
+        lcd_set_mode(LcdMode::MODE_HBLANK);
+    }
+}
+
+pub unsafe fn ppu_mode_hblank() {
+    if ppu_get_context().line_ticks < TICKS_PER_LINE {
+        return;
+    }
+
+    increment_ly();
+
+    if ppu_get_context().line >= YRES {
+        lcd_set_mode(LcdMode::MODE_VBLANK);
+        cpu_request_interrupt(InterruptType::IT_VBLANK);
+        ppu_get_context().current_frame += 1;
+    } else {
+        lcd_set_mode(LcdMode::MODE_OAM);
+    }
+}
+
+pub unsafe fn ppu_mode_vblank() {
+    let line = ppu_get_context().line;
+    let line_ticks = ppu_get_context().line_ticks;
+
+    if line == LINES_PER_FRAME - 1 && line_ticks == 4 {
+        lcd_get_context().ly = 0;
+    }
+
+    if line_ticks < TICKS_PER_LINE {
+        return;
+    }
+
+    increment_ly();
+
+    if ppu_get_context().line == 0 {
+        lcd_set_mode(LcdMode::MODE_OAM);
+    }
+}
+
+// Refreshes the LY=LYC flag and requests the STAT interrupt on a rising edge of the STAT line
+pub unsafe fn ppu_update_stat() {
+    let ctx = ppu_get_context();
+    let lcd = lcd_get_context();
+
+    let coincidence = lcd.ly == lcd.lyc;
+    bit_set(&mut lcd.stat, 2, coincidence);
+
+    let mode_source = match lcd_get_mode() {
+        LcdMode::MODE_HBLANK => bit(lcd.stat, 3),
+        // The OAM source also fires as line 144 starts
+        LcdMode::MODE_VBLANK => {
+            bit(lcd.stat, 4) || (ctx.line == YRES && ctx.line_ticks == 0 && bit(lcd.stat, 5))
+        }
+        LcdMode::MODE_OAM => bit(lcd.stat, 5),
+        LcdMode::MODE_XFER => false,
+    };
+
+    let stat_line = mode_source || (coincidence && bit(lcd.stat, 6));
+
+    if stat_line && !ctx.stat_line {
+        cpu_request_interrupt(InterruptType::IT_LCD_STAT);
+    }
+
+    ctx.stat_line = stat_line;
+}
@@ -1,26 +1,50 @@
 struct RamContext {
-    wram: [u8; 0x2000],
+    wram: [u8; 0x8000],
+    wram_bank: u8,
     hram: [u8; 0x80],
 }
 
 static mut CTX: RamContext = RamContext {
-    wram: [0; 0x2000],
+    wram: [0; 0x8000],
+    wram_bank: 1,
     hram: [0; 0x80],
 };
 
-pub unsafe fn wram_read(mut address: u16) -> u8 {
-    address -= 0xC000;
+// 0xC000 - 0xCFFF is always bank 0, 0xD000 - 0xDFFF is bank 1-7 (CGB) selected by SVBK
+unsafe fn wram_offset(address: u16) -> usize {
+    let address = (address - 0xC000) as usize;
 
-    if address >= 0x2000 {
+    if address < 0x1000 {
+        address
+    } else {
+        (CTX.wram_bank as usize) * 0x1000 + (address - 0x1000)
+    }
+}
+
+pub unsafe fn wram_read(address: u16) -> u8 {
+    if !(0xC000..0xE000).contains(&address) {
+        println!("INVALID WRAM ({:04X})", address);
+        return 0xFF;
+    }
+
+    CTX.wram[wram_offset(address)]
+}
+
+pub unsafe fn wram_write(address: u16, value: u8) {
+    if !(0xC000..0xE000).contains(&address) {
         println!("INVALID WRAM ({:04X})", address);
+        return;
     }
 
-    CTX.wram[address as usize]
+    CTX.wram[wram_offset(address)] = value;
+}
+
+pub unsafe fn wram_bank_read() -> u8 {
+    0xF8 | CTX.wram_bank
 }
 
-pub unsafe fn wram_write(mut address: u16, value: u8) {
-    address -= 0xC000;
-    CTX.wram[address as usize] = value;
+pub unsafe fn wram_bank_write(value: u8) {
+    CTX.wram_bank = (value & 0x07).max(1);
 }
 
 pub unsafe fn hram_read(mut address: u16) -> u8 {
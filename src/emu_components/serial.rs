@@ -0,0 +1,87 @@
+use super::common::bit;
+use super::emu::emu_cgb_mode;
+use super::interrupts::{cpu_request_interrupt, InterruptType};
+
+/*
+  0xFF01 SB : serial transfer data
+  0xFF02 SC : bit 7 transfer start, bit 1 clock speed (CGB), bit 0 internal clock
+
+  With the internal clock a bit is shifted out every 128 M-cycles
+  (8192 Hz), or every 4 M-cycles in CGB fast mode. Nothing is ever
+  connected to the link port, so 0xFF is shifted in.
+*/
+
+#[derive(Debug)]
+pub struct SerialContext {
+    pub sb: u8,
+    pub sc: u8,
+    bits_left: u8,
+    cycles: u16,
+}
+
+static mut CTX: SerialContext = SerialContext {
+    sb: 0,
+    sc: 0,
+    bits_left: 0,
+    cycles: 0,
+};
+
+pub unsafe fn serial_init() {
+    CTX.sb = 0;
+    // SC reads 0x7F in CGB mode and 0x7E otherwise, including CGB hardware running a DMG cart
+    CTX.sc = if emu_cgb_mode() { 0x03 } else { 0x00 };
+    CTX.bits_left = 0;
+    CTX.cycles = 0;
+}
+
+pub unsafe fn serial_tick() {
+    // Only an internally clocked transfer ever makes progress
+    if CTX.bits_left == 0 || !bit(CTX.sc, 0) {
+        return;
+    }
+
+    CTX.cycles += 1;
+
+    let period = if emu_cgb_mode() && bit(CTX.sc, 1) {
+        4
+    } else {
+        128
+    };
+
+    if CTX.cycles < period {
+        return;
+    }
+
+    CTX.cycles = 0;
+    CTX.sb = (CTX.sb << 1) | 1;
+    CTX.bits_left -= 1;
+
+    if CTX.bits_left == 0 {
+        CTX.sc &= 0x7F;
+        cpu_request_interrupt(InterruptType::IT_SERIAL);
+    }
+}
+
+pub unsafe fn serial_read(address: u16) -> u8 {
+    match address {
+        0xFF01 => CTX.sb,
+        _ if emu_cgb_mode() => CTX.sc | 0x7C,
+        _ => CTX.sc | 0x7E,
+    }
+}
+
+pub unsafe fn serial_write(address: u16, value: u8) {
+    match address {
+        0xFF01 => CTX.sb = value,
+        _ => {
+            CTX.sc = value;
+
+            if bit(value, 7) {
+                CTX.bits_left = 8;
+                CTX.cycles = 0;
+            } else {
+                CTX.bits_left = 0;
+            }
+        }
+    }
+}
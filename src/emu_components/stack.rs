@@ -85,7 +85,7 @@ use super::cpu_util::cpu_get_regs;
 
 pub unsafe fn stack_push(data: u8) {
     let mut regs = cpu_get_regs();
-    regs.sp -= 1;
+    regs.sp = regs.sp.wrapping_sub(1);
     bus_write(regs.sp, data);
 }
 
@@ -97,7 +97,7 @@ pub unsafe fn stack_push16(data: u16) {
 pub unsafe fn stack_pop() -> u8 {
     let regs = cpu_get_regs();
     let read = bus_read(regs.sp);
-    regs.sp += 1;
+    regs.sp = regs.sp.wrapping_add(1);
     read
 }
 
@@ -1,3 +1,170 @@
-pub fn timer_init() {}
+use super::boot::boot_rom_mapped;
+use super::common::bit;
+use super::emu::emu_model;
+use super::interrupts::{cpu_request_interrupt, InterruptType};
+use super::model::HardwareModel;
 
-pub fn timer_tick() {}
+/*
+  Timer registers:
+
+  0xFF04 DIV  : upper 8 bits of the 16-bit internal divider
+  0xFF05 TIMA : counter, incremented on a falling edge of the selected divider bit
+  0xFF06 TMA  : value reloaded into TIMA after it overflows
+  0xFF07 TAC  : bit 2 enable, bits 0-1 select the divider bit
+
+  TAC clock select:
+
+  00: bit 9 (4096 Hz)
+  01: bit 3 (262144 Hz)
+  10: bit 5 (65536 Hz)
+  11: bit 7 (16384 Hz)
+
+  When TIMA overflows it reads 0x00 for one M-cycle before TMA is loaded
+  and the timer interrupt is requested. Writing TIMA during that cycle
+  cancels the reload, writing TIMA on the reload cycle is ignored, and
+  writing TMA on the reload cycle is copied straight into TIMA.
+*/
+
+#[derive(Debug)]
+pub struct TimerContext {
+    pub div: u16,
+    pub tima: u8,
+    pub tma: u8,
+    pub tac: u8,
+
+    // T-cycles left until an overflowed TIMA is reloaded from TMA
+    overflow_ticks: u8,
+    // T-cycles left in the M-cycle during which TMA is copied into TIMA
+    reload_ticks: u8,
+}
+
+static mut CTX: TimerContext = TimerContext {
+    div: 0,
+    tima: 0,
+    tma: 0,
+    tac: 0,
+    overflow_ticks: 0,
+    reload_ticks: 0,
+};
+
+pub unsafe fn timer_init() {
+    CTX.div = if boot_rom_mapped() {
+        0
+    } else {
+        timer_post_boot_div(emu_model())
+    };
+    CTX.tima = 0;
+    CTX.tma = 0;
+    CTX.tac = 0xF8;
+    CTX.overflow_ticks = 0;
+    CTX.reload_ticks = 0;
+}
+
+/*
+  The divider starts from 0 at power-on and has run for as long as the boot
+  ROM did by the time it reaches 0x0100. DIV reads 0x18 on DMG0 and 0xAB on
+  DMG and MGB. The SGB and CGB boot ROMs take a cartridge dependent time,
+  their values are typical rather than exact.
+*/
+fn timer_post_boot_div(model: HardwareModel) -> u16 {
+    match model {
+        HardwareModel::DMG0 => 0x1830,
+        HardwareModel::DMG | HardwareModel::MGB => 0xABCC,
+        HardwareModel::SGB | HardwareModel::SGB2 => 0xD85C,
+        HardwareModel::CGB | HardwareModel::AGB => 0x1EA0,
+    }
+}
+
+fn timer_signal(div: u16, tac: u8) -> bool {
+    let shift = match tac & 0b11 {
+        0b00 => 9,
+        0b01 => 3,
+        0b10 => 5,
+        _ => 7,
+    };
+
+    bit(tac, 2) && (div >> shift) & 1 == 1
+}
+
+unsafe fn timer_increment() {
+    if CTX.tima == 0xFF {
+        CTX.tima = 0;
+        CTX.overflow_ticks = 4;
+    } else {
+        CTX.tima += 1;
+    }
+}
+
+pub unsafe fn timer_tick() {
+    if CTX.reload_ticks > 0 {
+        CTX.reload_ticks -= 1;
+    }
+
+    if CTX.overflow_ticks > 0 {
+        CTX.overflow_ticks -= 1;
+
+        if CTX.overflow_ticks == 0 {
+            CTX.tima = CTX.tma;
+            CTX.reload_ticks = 4;
+            cpu_request_interrupt(InterruptType::IT_TIMER);
+        }
+    }
+
+    let prev_div = CTX.div;
+    CTX.div = CTX.div.wrapping_add(1);
+
+    if timer_signal(prev_div, CTX.tac) && !timer_signal(CTX.div, CTX.tac) {
+        timer_increment();
+    }
+}
+
+pub unsafe fn timer_write(address: u16, value: u8) {
+    match address {
+        0xFF04 => {
+            //DIV, any write resets the whole divider
+            let was_high = timer_signal(CTX.div, CTX.tac);
+            CTX.div = 0;
+
+            if was_high {
+                timer_increment();
+            }
+        }
+        0xFF05 => {
+            //TIMA
+            if CTX.overflow_ticks > 0 {
+                CTX.overflow_ticks = 0;
+                CTX.tima = value;
+            } else if CTX.reload_ticks == 0 {
+                CTX.tima = value;
+            }
+        }
+        0xFF06 => {
+            //TMA
+            CTX.tma = value;
+
+            if CTX.reload_ticks > 0 {
+                CTX.tima = value;
+            }
+        }
+        0xFF07 => {
+            //TAC, switching off the selected bit can also produce a falling edge
+            let was_high = timer_signal(CTX.div, CTX.tac);
+            CTX.tac = value | 0xF8;
+
+            if was_high && !timer_signal(CTX.div, CTX.tac) {
+                timer_increment();
+            }
+        }
+        _ => (),
+    }
+}
+
+pub unsafe fn timer_read(address: u16) -> u8 {
+    match address {
+        0xFF04 => (CTX.div >> 8) as u8,
+        0xFF05 => CTX.tima,
+        0xFF06 => CTX.tma,
+        0xFF07 => CTX.tac,
+        _ => 0xFF,
+    }
+}